@@ -0,0 +1,121 @@
+//! Every pathfinder on generated mazes of growing size and key/door density.
+//!
+//! Save a baseline with `cargo bench -- --save-baseline <name>`
+//! and compare against it with `cargo bench -- --baseline <name>`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use lavirint::{
+    generate::{generate, Params},
+    pathfinder::{self, Limits, ParallelConfig, SearchStats},
+    Labyrinth, COLUMN_SIZE,
+};
+
+const ROWS: [usize; 5] = [2, 4, 8, 16, 32];
+const DENSITIES: [(&str, f64, f64); 3] = [
+    ("open", 0.0, 0.0),
+    ("sparse", 0.05, 0.03),
+    ("dense", 0.15, 0.08),
+];
+
+struct Case {
+    density: &'static str,
+    fields: usize,
+    labyrinth: Labyrinth,
+    graph: Vec<Vec<u8>>,
+    keys: Vec<bool>,
+}
+
+impl Case {
+    fn id(&self) -> BenchmarkId {
+        BenchmarkId::new(self.density, self.fields)
+    }
+    fn end(&self) -> usize {
+        self.fields - 1
+    }
+}
+
+fn corpus() -> Vec<Case> {
+    let mut cases = vec![];
+    for (density, keys, doors) in DENSITIES {
+        for rows in ROWS {
+            let labyrinth = generate(&Params {
+                rows,
+                keys,
+                doors,
+                loops: 0.1,
+                seed: rows as u64,
+            });
+            let (graph, keys, _) = labyrinth.pathfind_matrix();
+            cases.push(Case {
+                density,
+                fields: rows * COLUMN_SIZE,
+                labyrinth,
+                graph,
+                keys,
+            });
+        }
+    }
+    cases
+}
+
+fn solvers(c: &mut Criterion) {
+    let corpus = corpus();
+
+    let mut group = c.benchmark_group("a_star");
+    for case in corpus.iter() {
+        group.bench_with_input(case.id(), case, |b, case| {
+            b.iter(|| {
+                let mut stats = SearchStats::default();
+                pathfinder::a_star(
+                    0,
+                    case.end(),
+                    &case.graph,
+                    &Limits::default(),
+                    &mut stats,
+                    None,
+                )
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("bfs_shortest_path");
+    for case in corpus.iter() {
+        group.bench_with_input(case.id(), case, |b, case| {
+            b.iter(|| pathfinder::bfs_shortest_path(0, case.end(), &case.graph, &[], true))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("pathfind");
+    for case in corpus.iter() {
+        group.bench_with_input(case.id(), case, |b, case| {
+            b.iter(|| case.labyrinth.pathfind(0, case.end(), &Limits::default()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("parallel_backtrack");
+    group.sample_size(10);
+    for case in corpus.iter() {
+        group.bench_with_input(case.id(), case, |b, case| {
+            b.iter(|| {
+                let mut stats = SearchStats::default();
+                pathfinder::parallel_backtrack(
+                    0,
+                    case.end(),
+                    &case.graph,
+                    &case.keys,
+                    &ParallelConfig::default(),
+                    &Limits::default(),
+                    &mut stats,
+                    None,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solvers);
+criterion_main!(benches);
@@ -0,0 +1,261 @@
+use super::COLUMN_SIZE;
+use crate::pathfinder::{bfs_distances, bfs_shortest_path};
+use std::{collections::VecDeque, fmt};
+
+#[derive(Debug, Default, Clone)]
+pub struct Report {
+    pub fields: usize,
+    pub empty_fields: usize,
+    pub dead_ends: usize,
+    pub corridors: usize,
+    pub three_way_junctions: usize,
+    pub four_way_junctions: usize,
+    pub components: usize,
+    pub components_without_doors: usize,
+    pub keys: usize,
+    pub doors: usize,
+    pub unreachable_keys: Vec<usize>,
+    pub diameter: usize,
+    pub longest_shortest_path: Vec<usize>,
+    pub river: f64,        // average length of a dead end branch
+    pub straightness: f64, // share of corridors going straight through
+}
+
+fn neighbours(graph: &[Vec<u8>], field: usize) -> Vec<usize> {
+    (0..graph.len())
+        .filter(|n| graph[field][*n] != 0 || graph[*n][field] != 0)
+        .collect()
+}
+
+fn undirected(graph: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut graph = graph.to_owned();
+    #[allow(clippy::needless_range_loop)]
+    for a in 0..graph.len() {
+        for b in 0..a {
+            let edge = graph[a][b].max(graph[b][a]);
+            graph[a][b] = edge;
+            graph[b][a] = edge;
+        }
+    }
+    graph
+}
+
+fn count_components(graph: &[Vec<u8>], present: &[bool], ignore_doors: bool) -> usize {
+    let mut visited = vec![false; graph.len()];
+    let mut components = 0;
+    for field in 0..graph.len() {
+        if !present[field] || visited[field] {
+            continue;
+        }
+        components += 1;
+        for (f, d) in bfs_distances(field, graph, ignore_doors).iter().enumerate() {
+            if d.is_some() {
+                visited[f] = true;
+            }
+        }
+    }
+    components
+}
+
+/// Fields every field leads to, one-way passages only from the side they can be left
+fn successors(graph: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    graph
+        .iter()
+        .map(|row| (0..row.len()).filter(|to| row[*to] != 0).collect())
+        .collect()
+}
+
+/// Steps to every field reachable from `from`, through doors too
+fn distances(from: usize, successors: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let mut distances = vec![None; successors.len()];
+    distances[from] = Some(0);
+    let mut queue = VecDeque::from([(from, 0)]);
+    while let Some((field, distance)) = queue.pop_front() {
+        for next in &successors[field] {
+            if distances[*next].is_none() {
+                distances[*next] = Some(distance + 1);
+                queue.push_back((*next, distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+fn branch_length(graph: &[Vec<u8>], dead_end: usize) -> usize {
+    let (mut previous, mut current) = (dead_end, dead_end);
+    let mut length = 0;
+    loop {
+        let next: Vec<usize> = neighbours(graph, current)
+            .into_iter()
+            .filter(|n| *n != previous)
+            .collect();
+        if next.len() != 1 || (current != dead_end && next[0] == dead_end) {
+            return length;
+        }
+        length += 1;
+        (previous, current) = (current, next[0]);
+    }
+}
+
+pub fn analyze(start: usize, graph: &[Vec<u8>], keys: &[bool], ends: &[bool]) -> Report {
+    let degree: Vec<usize> = (0..graph.len())
+        .map(|f| neighbours(graph, f).len())
+        .collect();
+    let present: Vec<bool> = (0..graph.len())
+        .map(|f| degree[f] > 0 || keys[f] || ends[f])
+        .collect();
+    let mut report = Report {
+        fields: present.iter().filter(|p| **p).count(),
+        empty_fields: present.iter().filter(|p| !**p).count(),
+        dead_ends: degree.iter().filter(|d| **d == 1).count(),
+        corridors: degree.iter().filter(|d| **d == 2).count(),
+        three_way_junctions: degree.iter().filter(|d| **d == 3).count(),
+        four_way_junctions: degree.iter().filter(|d| **d >= 4).count(),
+        keys: keys.iter().filter(|k| **k).count(),
+        ..Default::default()
+    };
+    #[allow(clippy::needless_range_loop)]
+    for a in 0..graph.len() {
+        for b in 0..a {
+            if graph[a][b] == 255 || graph[b][a] == 255 {
+                report.doors += 1;
+            }
+        }
+    }
+
+    let both_ways = undirected(graph);
+    report.components = count_components(&both_ways, &present, true);
+    report.components_without_doors = count_components(&both_ways, &present, false);
+
+    let reachable = bfs_distances(start, graph, true);
+    report.unreachable_keys = (0..graph.len())
+        .filter(|f| keys[*f] && reachable[*f].is_none())
+        .collect();
+
+    // a breadth first search from every field over successor lists, O(fields²) like building
+    // the matrix, searching the matrix itself would be O(fields³)
+    let successors = successors(graph);
+    let mut farthest = (start, start, 0);
+    for from in (0..graph.len()).filter(|f| present[*f]) {
+        for (to, distance) in distances(from, &successors).iter().enumerate() {
+            match distance {
+                Some(d) if *d > farthest.2 => farthest = (from, to, *d),
+                _ => {}
+            }
+        }
+    }
+    report.diameter = farthest.2;
+    report.longest_shortest_path =
+        bfs_shortest_path(farthest.0, farthest.1, graph, &[], true).unwrap_or_default();
+
+    let branches: Vec<usize> = (0..graph.len())
+        .filter(|f| degree[*f] == 1)
+        .map(|f| branch_length(graph, f))
+        .collect();
+    if !branches.is_empty() {
+        report.river = branches.iter().sum::<usize>() as f64 / branches.len() as f64;
+    }
+    let straight = (0..graph.len())
+        .filter(|f| degree[*f] == 2)
+        .filter(|f| match neighbours(graph, *f)[..] {
+            [a, b] => b - a == 2 || b - a == 2 * COLUMN_SIZE,
+            _ => false,
+        })
+        .count();
+    if report.corridors > 0 {
+        report.straightness = straight as f64 / report.corridors as f64;
+    }
+    report
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"fields\":{},\"empty_fields\":{},\"dead_ends\":{},\"corridors\":{},",
+                "\"three_way_junctions\":{},\"four_way_junctions\":{},\"components\":{},",
+                "\"components_without_doors\":{},\"keys\":{},\"doors\":{},",
+                "\"unreachable_keys\":{:?},\"diameter\":{},\"longest_shortest_path\":{:?},",
+                "\"river\":{:.3},\"straightness\":{:.3}}}"
+            ),
+            self.fields,
+            self.empty_fields,
+            self.dead_ends,
+            self.corridors,
+            self.three_way_junctions,
+            self.four_way_junctions,
+            self.components,
+            self.components_without_doors,
+            self.keys,
+            self.doors,
+            self.unreachable_keys,
+            self.diameter,
+            self.longest_shortest_path,
+            self.river,
+            self.straightness,
+        )
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fields                    {}", self.fields)?;
+        writeln!(f, "empty fields              {}", self.empty_fields)?;
+        writeln!(f, "dead ends                 {}", self.dead_ends)?;
+        writeln!(f, "corridors                 {}", self.corridors)?;
+        writeln!(f, "three-way junctions       {}", self.three_way_junctions)?;
+        writeln!(f, "four-way junctions        {}", self.four_way_junctions)?;
+        writeln!(f, "components                {}", self.components)?;
+        writeln!(
+            f,
+            "components without doors  {}",
+            self.components_without_doors
+        )?;
+        writeln!(f, "keys                      {}", self.keys)?;
+        writeln!(f, "doors                     {}", self.doors)?;
+        writeln!(f, "unreachable keys          {:?}", self.unreachable_keys)?;
+        writeln!(f, "diameter                  {}", self.diameter)?;
+        writeln!(
+            f,
+            "longest shortest path     {:?}",
+            self.longest_shortest_path
+        )?;
+        writeln!(f, "river                     {:.3}", self.river)?;
+        writeln!(f, "straightness              {:.3}", self.straightness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_maze_report() {
+        let (graph, keys, ends) = crate::read_file("./labyrinth.txt")
+            .unwrap()
+            .pathfind_matrix();
+        let report = analyze(0, &graph, &keys, &ends);
+        // counted by hand on labyrinth.txt: the door east of 8 opens onto the border
+        assert_eq!((report.keys, report.doors), (11, 8));
+        assert_eq!((report.fields, report.empty_fields), (50, 4));
+        assert_eq!(report.dead_ends, 8); // 5, 8, 21, 23, 26, 28, 44 and 48
+        assert_eq!((report.corridors, report.three_way_junctions), (34, 8));
+        assert_eq!(report.components, 1);
+        assert!(report.unreachable_keys.is_empty());
+        assert_eq!(report.diameter, 31);
+        let path = &report.longest_shortest_path;
+        assert_eq!(path.len(), 32);
+        assert_eq!((path[0], path[31]), (8, 45));
+    }
+
+    #[test]
+    fn distances_follow_one_way_passages() {
+        let mut graph = vec![vec![0u8; 3]; 3];
+        graph[0][1] = 1; // one way from 0 to 1
+        graph[1][2] = 255;
+        graph[2][1] = 255;
+        let successors = successors(&graph);
+        assert_eq!(distances(0, &successors), [Some(0), Some(1), Some(2)]);
+        assert_eq!(distances(2, &successors), [None, Some(1), Some(0)]);
+    }
+}
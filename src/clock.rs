@@ -0,0 +1,45 @@
+//! `std::time::Instant` panics on wasm32-unknown-unknown, the solvers take the time from here so
+//! deadlines work in the browser too, where the host supplies the milliseconds
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use host::Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod host {
+    use std::{ops::Add, time::Duration};
+
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        /// Milliseconds from a monotonic clock, `performance.now()` in JS
+        fn lavirint_now() -> f64;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Instant(f64);
+
+    impl Instant {
+        pub fn now() -> Self {
+            // SAFETY: the import takes no arguments and only reads the clock
+            Instant(unsafe { lavirint_now() })
+        }
+
+        pub fn duration_since(&self, earlier: Instant) -> Duration {
+            Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Instant::now().duration_since(*self)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Instant;
+
+        fn add(self, duration: Duration) -> Instant {
+            Instant(self.0 + duration.as_secs_f64() * 1000.0)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{Labyrinth, COLUMN_SIZE};
+use std::{collections::HashSet, fmt::Write};
+
+/// The graph `pathfind_matrix` builds in Graphviz DOT. Nodes are the fields with a passage,
+/// labelled with their index and (row, col) and pinned to their place in the maze for
+/// `neato -n`. Two-way passages are drawn without arrows, one-way ones with an arrow, doors
+/// dashed. The walk, when given, is drawn in blue over the passages it takes
+pub fn to_dot(lab: &Labyrinth, walk: Option<&[usize]>) -> String {
+    let (graph, keys, ends) = lab.pathfind_matrix();
+    let walk = walk.unwrap_or_default();
+    let walked: HashSet<usize> = walk.iter().copied().collect();
+    let steps: HashSet<(usize, usize)> = walk
+        .windows(2)
+        .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+        .collect();
+
+    let mut dot = String::from("digraph labyrinth {\n");
+    dot.push_str("    node [shape=circle, fontname=\"sans-serif\", fontsize=10];\n");
+    for field in 0..graph.len() {
+        let connected = (0..graph.len()).any(|n| graph[field][n] != 0 || graph[n][field] != 0);
+        if !connected && !keys[field] && !ends[field] {
+            continue;
+        }
+        let (row, col) = (field / COLUMN_SIZE, field % COLUMN_SIZE);
+        let mut attributes = vec![
+            format!("label=\"{field}\\n({row},{col})\""),
+            format!("pos=\"{},{}!\"", col * 72, (lab.0.len() - row) * 72),
+        ];
+        if ends[field] {
+            attributes.push("shape=doublecircle, color=red".to_string());
+        } else if keys[field] {
+            attributes.push("shape=box".to_string());
+        }
+        match (keys[field], walked.contains(&field)) {
+            (true, true) => attributes.push("style=filled, fillcolor=\"gold:lightblue\"".into()),
+            (true, false) => attributes.push("style=filled, fillcolor=gold".into()),
+            (false, true) => attributes.push("style=filled, fillcolor=lightblue".into()),
+            (false, false) => {}
+        }
+        let _ = writeln!(dot, "    {field} [{}];", attributes.join(", "));
+    }
+    for a in 0..graph.len() {
+        #[allow(clippy::needless_range_loop)]
+        for b in a + 1..graph.len() {
+            let (forward, backward) = (graph[a][b], graph[b][a]);
+            let (from, to) = match (forward, backward) {
+                (0, 0) => continue,
+                (_, 0) => (a, b),
+                (0, _) => (b, a),
+                _ => (a, b),
+            };
+            let mut attributes = vec![];
+            if forward != 0 && backward != 0 {
+                attributes.push("dir=none");
+            }
+            if forward == 255 || backward == 255 {
+                attributes.push("style=dashed, color=sienna, label=\"door\", fontsize=8");
+            }
+            if steps.contains(&(a, b)) {
+                attributes.push("penwidth=3, color=blue");
+            }
+            let _ = writeln!(dot, "    {from} -> {to} [{}];", attributes.join(", "));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_nodes_doors_and_walk() {
+        let lab = crate::read_file("./labyrinth.txt").unwrap();
+        let dot = to_dot(&lab, Some(&[0, 9, 10]));
+        assert!(dot.starts_with("digraph labyrinth {") && dot.ends_with("}\n"));
+        assert!(dot.contains("    47 [label=\"47\\n(5,2)\""));
+        assert!(dot.contains("0 -> 1 [dir=none, style=dashed"));
+        assert!(dot.contains("0 -> 9 [dir=none, penwidth=3, color=blue]"));
+        assert!(!dot.contains("    30 ["), "field 30 has no passages");
+        assert_eq!(dot.matches("doublecircle").count(), 2);
+    }
+}
@@ -0,0 +1,450 @@
+use crate::{
+    pathfinder::{Limits, Outcome},
+    render::{BoxRenderer, Renderer},
+    solver::{BfsSolver, Problem, Rules, Solver},
+    terminal::{self, Key, RawMode},
+    theme::{Colors, Theme},
+    write_file, Directions, Edge, Field, Labyrinth, COLUMN_SIZE,
+};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+const CURSOR: &str = "<>";
+const HELP: &str =
+    "arrows move, wasd cycle wall/path/one-way out/one-way in/door, k key, e end, g start, x clear, +/- rows, enter saves, q quits";
+
+/// The solver runs after every change, a maze it can't finish in time shows as unsolved
+const SOLVE_TIME: Duration = Duration::from_millis(200);
+
+/// An edge as seen from the field under the cursor, one-way passages lead out of it or into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Passage {
+    Wall,
+    Path,
+    Out,
+    In,
+    Door,
+}
+
+impl Passage {
+    /// Wall, path, one-way out, one-way in, door, then wall again
+    fn next(self) -> Passage {
+        match self {
+            Passage::Wall => Passage::Path,
+            Passage::Path => Passage::Out,
+            Passage::Out => Passage::In,
+            Passage::In => Passage::Door,
+            Passage::Door => Passage::Wall,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Passage::Wall => "wall",
+            Passage::Path => "path",
+            Passage::Out => "one-way out",
+            Passage::In => "one-way in",
+            Passage::Door => "door",
+        }
+    }
+}
+
+const DIRECTIONS: [(&str, (isize, isize)); 4] = [
+    ("north", (-1, 0)),
+    ("south", (1, 0)),
+    ("west", (0, -1)),
+    ("east", (0, 1)),
+];
+
+fn side(directions: &Directions<bool>, direction: (isize, isize)) -> bool {
+    match direction {
+        (-1, _) => directions.north,
+        (1, _) => directions.south,
+        (_, -1) => directions.west,
+        _ => directions.east,
+    }
+}
+
+fn side_mut(directions: &mut Directions<bool>, direction: (isize, isize)) -> &mut bool {
+    match direction {
+        (-1, _) => &mut directions.north,
+        (1, _) => &mut directions.south,
+        (_, -1) => &mut directions.west,
+        _ => &mut directions.east,
+    }
+}
+
+/// A maze being edited, every change keeps both sides of an edge in sync
+pub struct Editor {
+    pub lab: Labyrinth,
+    pub cursor: usize,
+    pub start: usize,
+    pub rules: Rules,
+}
+
+impl Editor {
+    pub fn new(lab: Labyrinth, start: usize, rules: Rules) -> Self {
+        Editor {
+            lab,
+            cursor: start,
+            start,
+            rules,
+        }
+    }
+
+    fn fields(&self) -> usize {
+        self.lab.0.len() * COLUMN_SIZE
+    }
+
+    fn field_mut(&mut self, index: usize) -> &mut Field {
+        &mut self.lab.0[index / COLUMN_SIZE][index % COLUMN_SIZE]
+    }
+
+    fn neighbour(&self, field: usize, (dr, dc): (isize, isize)) -> Option<usize> {
+        let row = (field / COLUMN_SIZE).checked_add_signed(dr)?;
+        let col = (field % COLUMN_SIZE).checked_add_signed(dc)?;
+        (row < self.lab.0.len() && col < COLUMN_SIZE).then_some(row * COLUMN_SIZE + col)
+    }
+
+    /// Moves the cursor, it stays put at the border
+    pub fn move_cursor(&mut self, direction: (isize, isize)) {
+        if let Some(to) = self.neighbour(self.cursor, direction) {
+            self.cursor = to;
+        }
+    }
+
+    fn field(&self, index: usize) -> &Field {
+        &self.lab.0[index / COLUMN_SIZE][index % COLUMN_SIZE]
+    }
+
+    /// The edge from the cursor towards the direction, `None` towards the border
+    pub fn passage(&self, direction: (isize, isize)) -> Option<Passage> {
+        let other = self.neighbour(self.cursor, direction)?;
+        let a = self.cursor.min(other);
+        let (row, col) = (a / COLUMN_SIZE, a % COLUMN_SIZE);
+        let edge = match other.abs_diff(self.cursor) {
+            1 => self.lab.east_edge(row, col),
+            _ => self.lab.south_edge(row, col),
+        };
+        let out = side(&self.field(self.cursor).one_way, direction);
+        let into = side(&self.field(other).one_way, (-direction.0, -direction.1));
+        Some(match (edge, out, into) {
+            (Edge::Wall, ..) => Passage::Wall,
+            (Edge::Door, ..) => Passage::Door,
+            (Edge::Path, true, false) => Passage::Out,
+            (Edge::Path, false, true) => Passage::In,
+            (Edge::Path, ..) => Passage::Path,
+        })
+    }
+
+    /// Cycles the edge from the cursor towards the direction through wall, path, one-way out
+    /// of the field, one-way into it and door, `None` towards the border
+    pub fn cycle_edge(&mut self, direction: (isize, isize)) -> Option<Passage> {
+        let passage = self.passage(direction)?.next();
+        let other = self.neighbour(self.cursor, direction)?;
+        let a = self.cursor.min(other);
+        let (row, col) = (a / COLUMN_SIZE, a % COLUMN_SIZE);
+        let edge = match passage {
+            Passage::Wall => Edge::Wall,
+            Passage::Path | Passage::Out | Passage::In => Edge::Path,
+            Passage::Door => Edge::Door,
+        };
+        match other.abs_diff(self.cursor) {
+            1 => self.lab.set_east_edge(row, col, edge),
+            _ => self.lab.set_south_edge(row, col, edge),
+        }
+        let back = (-direction.0, -direction.1);
+        match passage {
+            Passage::Out => *side_mut(&mut self.field_mut(self.cursor).one_way, direction) = true,
+            Passage::In => *side_mut(&mut self.field_mut(other).one_way, back) = true,
+            _ => {}
+        }
+        Some(passage)
+    }
+
+    pub fn toggle_key(&mut self) {
+        let field = self.field_mut(self.cursor);
+        field.contains_key = !field.contains_key;
+    }
+
+    pub fn toggle_end(&mut self) {
+        let field = self.field_mut(self.cursor);
+        field.is_end = !field.is_end;
+    }
+
+    /// Walls the field in on all sides and removes its key and end
+    pub fn clear(&mut self) {
+        for direction in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            while self
+                .cycle_edge(direction)
+                .map_or(false, |p| p != Passage::Wall)
+            {}
+        }
+        *self.field_mut(self.cursor) = Field::default();
+    }
+
+    pub fn add_row(&mut self) {
+        self.lab.0.push(vec![Field::default(); COLUMN_SIZE]);
+    }
+
+    /// Removes the last row and the edges leading into it, the maze keeps at least one row
+    pub fn remove_row(&mut self) -> bool {
+        let rows = self.lab.0.len();
+        if rows <= 1 {
+            return false;
+        }
+        for col in 0..COLUMN_SIZE {
+            self.lab.set_south_edge(rows - 2, col, Edge::Wall);
+        }
+        self.lab.0.pop();
+        let last = self.fields() - 1;
+        self.cursor = self.cursor.min(last);
+        self.start = self.start.min(last);
+        true
+    }
+
+    /// The shortest walk from the start to an end, `None` when there is none or the solver
+    /// ran out of time
+    pub fn solve(&self) -> Option<Vec<usize>> {
+        let problem = Problem {
+            start: self.start,
+            rules: self.rules.clone(),
+            limits: Limits::timeout(SOLVE_TIME),
+            ..Default::default()
+        };
+        match BfsSolver.solve(&self.lab, &problem).outcome {
+            Outcome::Complete(solution) => solution.map(|s| s.walk),
+            Outcome::Partial(_) => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let field = self.field(self.cursor);
+        let mut marks = vec![];
+        if self.cursor == self.start {
+            marks.push(String::from("start"));
+        }
+        if field.contains_key {
+            marks.push(String::from("key"));
+        }
+        if field.is_end {
+            marks.push(String::from("end"));
+        }
+        for (name, direction) in DIRECTIONS {
+            match self.passage(direction) {
+                Some(Passage::Out) => marks.push(format!("one-way {name}")),
+                Some(Passage::In) => marks.push(format!("one-way from {name}")),
+                _ => {}
+            }
+        }
+        format!("field {} [{}] {}", self.cursor, field, marks.join(" "))
+    }
+}
+
+fn draw(editor: &Editor, theme: Option<&Theme>, walk: &[usize]) -> io::Result<()> {
+    let renderer = BoxRenderer {
+        indices: true,
+        colors: theme.map(|theme| Colors {
+            theme: theme.clone(),
+            walk,
+        }),
+        ..Default::default()
+    };
+    let mut out = io::stdout().lock();
+    terminal::move_to(0, 0);
+    for line in renderer.to_string(&editor.lab).lines() {
+        writeln!(out, "{line}\x1b[K")?;
+    }
+    write!(out, "\x1b[J")?;
+    // over the center of the field's box, the status line tells what it hides
+    let (row, col) = (editor.cursor / COLUMN_SIZE, editor.cursor % COLUMN_SIZE);
+    write!(
+        out,
+        "\x1b[{};{}H\x1b[7m{CURSOR}\x1b[0m",
+        row * 4 + 2,
+        col * 6 + 3
+    )?;
+    out.flush()
+}
+
+/// Edits the maze in the terminal and saves it to `path`, solving it again after every change
+pub fn edit(
+    lab: Labyrinth,
+    path: &str,
+    start: usize,
+    rules: Rules,
+    theme: Option<&Theme>,
+) -> io::Result<()> {
+    if start >= lab.0.len() * COLUMN_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("start {start} is outside the maze"),
+        ));
+    }
+    let mut editor = Editor::new(lab, start, rules);
+    let mut walk = editor.solve();
+    let mut message = String::from(HELP);
+    let mut saved = true;
+    let mut quitting = false;
+    let raw = RawMode::enter()?;
+    terminal::clear_screen();
+    loop {
+        draw(&editor, theme, walk.as_deref().unwrap_or_default())?;
+        let status_row = editor.lab.0.len() * 4;
+        let solved = match &walk {
+            Some(walk) => format!("shortest walk {} move(s)", walk.len().saturating_sub(1)),
+            None => String::from("no walk to an end"),
+        };
+        terminal::status_line(
+            status_row,
+            &format!(
+                "{}  {solved}{}",
+                editor.describe(),
+                if saved { "" } else { "  (unsaved)" }
+            ),
+        );
+        terminal::status_line(status_row + 1, &message);
+        io::stdout().flush()?;
+        let key = loop {
+            if let Some(key) = raw.read_key()? {
+                break key;
+            }
+        };
+        message = String::new();
+        let changed = match key {
+            Key::Up | Key::Down | Key::Left | Key::Right => {
+                editor.move_cursor(key.direction().expect("arrows have directions"));
+                false
+            }
+            Key::Char('w' | 'a' | 's' | 'd') => {
+                match key.direction().and_then(|d| editor.cycle_edge(d)) {
+                    Some(passage) => {
+                        message = String::from(passage.name());
+                        true
+                    }
+                    None => {
+                        message = String::from("the border is always a wall");
+                        false
+                    }
+                }
+            }
+            Key::Char('k') => {
+                editor.toggle_key();
+                true
+            }
+            Key::Char('e') => {
+                editor.toggle_end();
+                true
+            }
+            Key::Char('x') => {
+                editor.clear();
+                true
+            }
+            Key::Char('g') => {
+                editor.start = editor.cursor;
+                true
+            }
+            Key::Char('+') => {
+                editor.add_row();
+                true
+            }
+            Key::Char('-') => editor.remove_row(),
+            Key::Enter => {
+                write_file(&editor.lab, path)?;
+                saved = true;
+                message = format!("saved to {path}");
+                false
+            }
+            Key::Char('q') | Key::Escape if saved || quitting => break,
+            Key::Char('q') | Key::Escape => {
+                quitting = true;
+                message = String::from("unsaved changes, press q again to quit without saving");
+                false
+            }
+            _ => false,
+        };
+        if changed {
+            saved = false;
+            quitting = false;
+            walk = editor.solve();
+        }
+    }
+    drop(raw);
+    terminal::move_to(editor.lab.0.len() * 4, 0);
+    print!("\x1b[J");
+    io::stdout().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty(rows: usize) -> Editor {
+        Editor::new(
+            Labyrinth(vec![vec![Field::default(); COLUMN_SIZE]; rows]),
+            0,
+            Rules::default(),
+        )
+    }
+
+    #[test]
+    fn edges_cycle_on_both_sides() {
+        let mut editor = empty(2);
+        editor.cursor = 1;
+        assert_eq!(editor.cycle_edge((0, -1)), Some(Passage::Path));
+        assert_eq!(editor.lab.east_edge(0, 0), Edge::Path);
+        assert_eq!(editor.cycle_edge((0, -1)), Some(Passage::Out));
+        assert!(editor.lab.0[0][1].one_way.west && !editor.lab.0[0][0].one_way.east);
+        assert_eq!(editor.cycle_edge((0, -1)), Some(Passage::In));
+        assert!(!editor.lab.0[0][1].one_way.west && editor.lab.0[0][0].one_way.east);
+        assert_eq!(editor.cycle_edge((0, -1)), Some(Passage::Door));
+        assert_eq!(editor.lab.east_edge(0, 0), Edge::Door);
+        assert!(!editor.lab.0[0][0].one_way.east);
+        assert_eq!(editor.cycle_edge((0, -1)), Some(Passage::Wall));
+        assert!(editor.lab.0[0][0].is_empty() && editor.lab.0[0][1].is_empty());
+        assert_eq!(editor.cycle_edge((-1, 0)), None);
+    }
+
+    #[test]
+    fn one_way_passages_are_read_from_both_sides() {
+        // 0 -> 9 drops south and can't be climbed back
+        let mut editor = empty(2);
+        editor.lab.set_south_edge(0, 0, Edge::Path);
+        editor.lab.0[0][0].one_way.south = true;
+        assert_eq!(editor.passage((1, 0)), Some(Passage::Out));
+        assert!(editor.describe().contains("one-way south"));
+        editor.move_cursor((1, 0));
+        assert_eq!(editor.passage((-1, 0)), Some(Passage::In));
+        assert!(editor.describe().contains("one-way from north"));
+        // cycling goes on from the one-way state instead of making it two-way
+        assert_eq!(editor.cycle_edge((-1, 0)), Some(Passage::Door));
+        editor.move_cursor((-1, 0));
+        editor.toggle_end();
+        editor.start = 9;
+        assert_eq!(editor.solve(), None);
+    }
+
+    #[test]
+    fn solves_after_changes() {
+        let mut editor = empty(2);
+        assert_eq!(editor.solve(), None);
+        editor.cycle_edge((1, 0));
+        editor.move_cursor((1, 0));
+        for _ in 0..4 {
+            editor.cycle_edge((0, 1)); // path, one-way out, one-way in, door
+        }
+        editor.toggle_key(); // the key for the door east of field 9
+        editor.move_cursor((0, 1));
+        editor.toggle_end();
+        assert_eq!(editor.solve(), Some(vec![0, 9, 10]));
+        editor.move_cursor((0, -1));
+        editor.toggle_key();
+        assert_eq!(editor.solve(), None);
+        editor.clear();
+        assert!(editor.lab.0[0][0].is_empty() && editor.lab.0[1][1].is_empty());
+        assert!(editor.remove_row() && !editor.remove_row());
+        assert_eq!(editor.cursor, 8);
+    }
+}
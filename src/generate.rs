@@ -0,0 +1,146 @@
+use super::{Edge, Field, Labyrinth, COLUMN_SIZE};
+
+/// Small xorshift generator, mazes only need to be reproducible from a seed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Params {
+    pub rows: usize,
+    pub keys: f64,  // chance of a field holding a key
+    pub doors: f64, // chance of a passage being a door
+    pub loops: f64, // chance of a wall being knocked down after the maze is carved
+    pub seed: u64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            rows: 6,
+            keys: 0.1,
+            doors: 0.1,
+            loops: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+fn connect(lab: &mut Labyrinth, a: usize, b: usize, door: bool) {
+    let (a, b) = (a.min(b), a.max(b));
+    let edge = match door {
+        true => Edge::Door,
+        false => Edge::Path,
+    };
+    match b == a + 1 {
+        true => lab.set_east_edge(a / COLUMN_SIZE, a % COLUMN_SIZE, edge),
+        false => lab.set_south_edge(a / COLUMN_SIZE, a % COLUMN_SIZE, edge),
+    }
+}
+
+fn connected(lab: &Labyrinth, a: usize, b: usize) -> bool {
+    let (a, b) = (a.min(b), a.max(b));
+    let edge = match b == a + 1 {
+        true => lab.east_edge(a / COLUMN_SIZE, a % COLUMN_SIZE),
+        false => lab.south_edge(a / COLUMN_SIZE, a % COLUMN_SIZE),
+    };
+    edge != Edge::Wall
+}
+
+fn neighbours(field: usize, rows: usize) -> Vec<usize> {
+    let (r, c) = (field / COLUMN_SIZE, field % COLUMN_SIZE);
+    let mut v = Vec::with_capacity(4);
+    if c > 0 {
+        v.push(field - 1);
+    }
+    if c < COLUMN_SIZE - 1 {
+        v.push(field + 1);
+    }
+    if r > 0 {
+        v.push(field - COLUMN_SIZE);
+    }
+    if r < rows - 1 {
+        v.push(field + COLUMN_SIZE);
+    }
+    v
+}
+
+/// Carves a random spanning tree from field 0 with the end in the last field,
+/// then adds loops, doors and keys
+pub fn generate(params: &Params) -> Labyrinth {
+    let rows = params.rows.max(1);
+    let len = rows * COLUMN_SIZE;
+    let mut rng = Rng::new(params.seed);
+    let mut lab = Labyrinth(vec![vec![Field::default(); COLUMN_SIZE]; rows]);
+    let mut visited = vec![false; len];
+    let mut stack = vec![0usize];
+    visited[0] = true;
+    while let Some(&current) = stack.last() {
+        let unvisited: Vec<usize> = neighbours(current, rows)
+            .into_iter()
+            .filter(|n| !visited[*n])
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[rng.below(unvisited.len())];
+        connect(&mut lab, current, next, rng.chance(params.doors));
+        visited[next] = true;
+        stack.push(next);
+    }
+    for field in 0..len {
+        for n in neighbours(field, rows).into_iter().filter(|n| *n > field) {
+            if !connected(&lab, field, n) && rng.chance(params.loops) {
+                connect(&mut lab, field, n, rng.chance(params.doors));
+            }
+        }
+        if field != 0 && field != len - 1 && rng.chance(params.keys) {
+            lab.0[field / COLUMN_SIZE][field % COLUMN_SIZE].contains_key = true;
+        }
+    }
+    lab.0[rows - 1][COLUMN_SIZE - 1].is_end = true;
+    lab
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doors_are_real_door_edges() {
+        let edges = |doors: f64| {
+            let lab = generate(&Params {
+                rows: 8,
+                doors,
+                seed: 3,
+                ..Default::default()
+            });
+            let (graph, _, _) = lab.pathfind_matrix();
+            let count = |value: u8| graph.iter().flatten().filter(|v| **v == value).count();
+            (count(1), count(255))
+        };
+        // a spanning tree of 72 fields has 71 edges, each counted from both sides
+        assert_eq!(edges(0.0).1, 0);
+        assert_eq!(edges(1.0).0, 0);
+        assert!(edges(1.0).1 >= 2 * 71);
+        let (paths, doors) = edges(0.3);
+        assert!(paths > 0 && doors > 0);
+    }
+}
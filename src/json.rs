@@ -0,0 +1,269 @@
+/// Just enough JSON to read requests, output is written with `format!` like everywhere else
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Whole non-negative numbers only
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 && *n <= usize::MAX as f64 => {
+                Some(*n as usize)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Escapes a string for a JSON string literal, quotes not included
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Nesting deeper than this is refused rather than risking the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, what: &str) -> Result<T, String> {
+        Err(format!("{what} at byte {}", self.at))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn eat(&mut self, literal: &str) -> bool {
+        let found = self.text[self.at..].starts_with(literal);
+        if found {
+            self.at += literal.len();
+        }
+        found
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return self.error("too deeply nested");
+        }
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(b'n') if self.eat("null") => Json::Null,
+            Some(b't') if self.eat("true") => Json::Bool(true),
+            Some(b'f') if self.eat("false") => Json::Bool(false),
+            Some(b'"') => Json::String(self.string()?),
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        self.skip_whitespace();
+                        if self.eat("]") {
+                            break;
+                        } else if !self.eat(",") {
+                            return self.error("expected , or ]");
+                        }
+                    }
+                }
+                Json::Array(items)
+            }
+            Some(b'{') => {
+                self.at += 1;
+                let mut pairs = vec![];
+                self.skip_whitespace();
+                if !self.eat("}") {
+                    loop {
+                        self.skip_whitespace();
+                        if self.peek() != Some(b'"') {
+                            return self.error("expected a key");
+                        }
+                        let key = self.string()?;
+                        self.skip_whitespace();
+                        if !self.eat(":") {
+                            return self.error("expected :");
+                        }
+                        pairs.push((key, self.value(depth + 1)?));
+                        self.skip_whitespace();
+                        if self.eat("}") {
+                            break;
+                        } else if !self.eat(",") {
+                            return self.error("expected , or }");
+                        }
+                    }
+                }
+                Json::Object(pairs)
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let rest = &self.text[self.at..];
+                let len = rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(rest.len());
+                match rest[..len].parse() {
+                    Ok(n) => {
+                        self.at += len;
+                        Json::Number(n)
+                    }
+                    Err(_) => return self.error("invalid number"),
+                }
+            }
+            _ => return self.error("expected a value"),
+        };
+        Ok(value)
+    }
+
+    /// The string starting at the opening quote
+    fn string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let mut s = String::new();
+        loop {
+            let rest = &self.text[self.at..];
+            let Some(c) = rest.chars().next() else {
+                return self.error("unterminated string");
+            };
+            self.at += c.len_utf8();
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let code = self
+                                .text
+                                .get(self.at + 1..self.at + 5)
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            // surrogate pairs aren't needed for maze files
+                            match code.and_then(char::from_u32) {
+                                Some(c) => {
+                                    self.at += 4;
+                                    c
+                                }
+                                None => return self.error("invalid \\u escape"),
+                            }
+                        }
+                        _ => return self.error("invalid escape"),
+                    };
+                    self.at += 1;
+                    s.push(escaped);
+                }
+                c if (c as u32) < 0x20 => return self.error("control character in string"),
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser { text, at: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    match parser.at == text.len() {
+        true => Ok(value),
+        false => parser.error("trailing characters"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requests() {
+        let json = parse(
+            r#" {"maze": "0101 0100 0000\n", "goals": [47, 3], "ignore_doors": true, "x": null} "#,
+        )
+        .unwrap();
+        assert_eq!(
+            json.get("maze").and_then(Json::as_str),
+            Some("0101 0100 0000\n")
+        );
+        let goals: Vec<usize> = json
+            .get("goals")
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .filter_map(Json::as_usize)
+            .collect();
+        assert_eq!(goals, [47, 3]);
+        assert_eq!(json.get("ignore_doors").and_then(Json::as_bool), Some(true));
+        assert_eq!(json.get("x"), Some(&Json::Null));
+        assert_eq!(parse(r#""é\"""#), Ok(Json::String("é\"".to_string())));
+        assert_eq!(parse("-1.5e2"), Ok(Json::Number(-150.0)));
+        assert_eq!(Json::Number(1.5).as_usize(), None);
+        for bad in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "\"open",
+            "tru",
+            "1 2",
+            &"[".repeat(100),
+        ] {
+            assert!(parse(bad).is_err(), "{bad}");
+        }
+        assert_eq!(escape("a\"b\\\n\u{1}"), "a\\\"b\\\\\\n\\u0001");
+    }
+}
@@ -0,0 +1,577 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use pathfinder::{Limits, Outcome};
+use render::Renderer;
+use theme::{paint, Part, Theme};
+
+pub const COLUMN_SIZE: usize = 9;
+
+pub mod analysis;
+pub mod clock;
+pub mod dot;
+pub mod edit;
+pub mod generate;
+pub mod json;
+pub mod pathfinder;
+pub mod play;
+pub mod raster;
+pub mod render;
+pub mod replay;
+pub mod server;
+pub mod solver;
+pub mod svg;
+pub mod terminal;
+pub mod theme;
+pub mod visualize;
+pub mod wasm;
+
+pub struct Labyrinth(pub Vec<Vec<Field>>);
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Directions<T> {
+    pub west: T,
+    pub east: T,
+    pub north: T,
+    pub south: T,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Field {
+    pub paths: Directions<bool>,
+    pub doors: Directions<bool>,
+    pub one_way: Directions<bool>, // passage can only be used to leave this field
+    pub contains_key: bool,
+    pub is_end: bool,
+}
+
+impl Directions<bool> {
+    pub fn any(&self) -> bool {
+        self.west || self.east || self.north || self.south
+    }
+}
+
+impl Field {
+    /// No passages or doors at all, the field isn't part of the maze
+    pub fn is_empty(&self) -> bool {
+        !self.paths.any() && !self.doors.any()
+    }
+}
+
+impl FromStr for Directions<bool> {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        macro_rules! bit_match {
+            ($slice:ident[$index:literal] -> $var:expr) => {
+                match $slice.chars().nth($index) {
+                    Some('0') => $var = false,
+                    Some('1') => $var = true,
+                    _ => return Err(()),
+                }
+            };
+        }
+        if s.len() > 4 {
+            Err(())
+        } else {
+            let mut dir = Self::default();
+            bit_match!(s[0] -> dir.west);
+            bit_match!(s[1] -> dir.east);
+            bit_match!(s[2] -> dir.north);
+            bit_match!(s[3] -> dir.south);
+            Ok(dir)
+        }
+    }
+}
+
+/// The bits towards west, east, north and south, as `FromStr` reads them
+impl fmt::Display for Directions<bool> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bit in [self.west, self.east, self.north, self.south] {
+            write!(f, "{}", bit as u8)?;
+        }
+        Ok(())
+    }
+}
+
+fn str_bitwise_and(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c == '1')
+}
+
+impl FromStr for Field {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 14 && s.len() != 19 {
+            return Err(());
+        }
+        Ok(Self {
+            paths: Directions::from_str(&s[0..4])?,
+            doors: Directions::from_str(&s[5..9])?,
+            one_way: match s.len() {
+                19 => Directions::from_str(&s[15..19])?,
+                _ => Directions::default(),
+            },
+            contains_key: str_bitwise_and(&s[10..12]),
+            is_end: str_bitwise_and(&s[12..14]),
+        })
+    }
+}
+
+/// One line of the maze file, the one-way bits only when there are any
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |set: bool| match set {
+            true => "11",
+            false => "00",
+        };
+        write!(
+            f,
+            "{} {} {}{}",
+            self.paths,
+            self.doors,
+            flag(self.contains_key),
+            flag(self.is_end)
+        )?;
+        if self.one_way.any() {
+            write!(f, " {}", self.one_way)?;
+        }
+        Ok(())
+    }
+}
+
+/// The maze file, one field per line, row by row
+impl fmt::Display for Labyrinth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in self.0.iter().flatten() {
+            writeln!(f, "{field}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_file(lab: &Labyrinth, path: &str) -> std::io::Result<()> {
+    std::fs::write(path, lab.to_string())
+}
+
+/// The maze file, every line a field, fields that don't fill a whole row are an error
+impl FromStr for Labyrinth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                Field::from_str(line.trim_end())
+                    .map_err(|_| format!("line {} isn't a field: {line:?}", i + 1))
+            })
+            .collect::<Result<Vec<Field>, _>>()?;
+        if fields.len() % COLUMN_SIZE != 0 {
+            return Err(format!(
+                "{} fields don't fill rows of {COLUMN_SIZE}",
+                fields.len()
+            ));
+        }
+        Ok(Labyrinth(
+            fields.chunks(COLUMN_SIZE).map(|row| row.to_vec()).collect(),
+        ))
+    }
+}
+
+pub fn read_file(path: &str) -> Result<Labyrinth, Box<dyn Error>> {
+    Ok(std::fs::read_to_string(path)?.parse()?)
+}
+
+pub const HEAT: [&str; 4] = ["░░", "▒▒", "▓▓", "██"];
+
+/// The four lines of one field's box, `center` goes inside the box and `label` under it
+pub fn field_lines(field: &Field, center: &str, label: &str) -> [String; 4] {
+    field_lines_painted(field, center, label, None)
+}
+
+/// Like [`field_lines`] with the walls and doors in the theme's colors, `center` and `label`
+/// are expected to be painted already
+pub fn field_lines_painted(
+    field: &Field,
+    center: &str,
+    label: &str,
+    theme: Option<&Theme>,
+) -> [String; 4] {
+    if field.is_empty() {
+        return std::array::from_fn(|_| " ".repeat(6));
+    }
+    let wall = |text: &str| paint(theme, Part::Wall, text);
+    let side = |door: bool, path: bool, [door_glyph, path_glyph, wall_glyph]: [&str; 3]| {
+        if door {
+            paint(theme, Part::Door, door_glyph)
+        } else if path {
+            wall(path_glyph)
+        } else {
+            wall(wall_glyph)
+        }
+    };
+    let (paths, doors) = (field.paths, field.doors);
+    [
+        format!(
+            "{}{}{}",
+            wall("┏━"),
+            side(doors.north, paths.north, ["╩╩", "┛┗", "━━"]),
+            wall("━┓")
+        ),
+        format!(
+            "{} {} {}",
+            side(doors.west, paths.west, ["╣", "┛", "┃"]),
+            center,
+            side(doors.east, paths.east, ["╠", "┗", "┃"]),
+        ),
+        format!(
+            "{} {:>2} {}",
+            side(doors.west, paths.west, ["╣", "┓", "┃"]),
+            label,
+            side(doors.east, paths.east, ["╠", "┏", "┃"]),
+        ),
+        format!(
+            "{}{}{}",
+            wall("┗━"),
+            side(doors.south, paths.south, ["╦╦", "┓┏", "━━"]),
+            wall("━┛")
+        ),
+    ]
+}
+
+/// What a field shows inside its box
+pub fn field_center(field: &Field) -> &'static str {
+    if field.is_end {
+        "🚩"
+    } else if field.contains_key {
+        "🗝️ "
+    } else {
+        "  "
+    }
+}
+
+/// With `heat` every field is shaded by the keys spent to reach it and shows its distance
+pub fn display_labyrinth(lab: &Labyrinth, heat: Option<&[Option<(usize, usize)>]>) {
+    let renderer = render::BoxRenderer {
+        indices: true,
+        heat,
+        ..Default::default()
+    };
+    renderer
+        .render(lab, &mut std::io::stdout().lock())
+        .expect("Error writing to stdout");
+}
+
+/// How two neighbouring fields are joined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Wall,
+    Path,
+    Door,
+}
+
+/// Same rule as `pathfind_matrix`: both fields declare the passage and at most one the door
+fn edge(a: (bool, bool), b: (bool, bool)) -> Edge {
+    let weight = |(path, door): (bool, bool)| 2 * path as u8 + 3 * door as u8;
+    match weight(a) + weight(b) {
+        4 => Edge::Path,
+        7 => Edge::Door,
+        _ => Edge::Wall,
+    }
+}
+
+/// (path, door) bits for the field declaring the edge and for its neighbour
+fn edge_bits(edge: Edge) -> ((bool, bool), (bool, bool)) {
+    match edge {
+        Edge::Wall => ((false, false), (false, false)),
+        Edge::Path => ((true, false), (true, false)),
+        Edge::Door => ((true, true), (true, false)),
+    }
+}
+
+impl Labyrinth {
+    /// Between the field and its east neighbour, a wall on the last column
+    pub fn east_edge(&self, row: usize, col: usize) -> Edge {
+        match col + 1 < COLUMN_SIZE {
+            true => {
+                let (field, east) = (&self.0[row][col], &self.0[row][col + 1]);
+                edge(
+                    (field.paths.east, field.doors.east),
+                    (east.paths.west, east.doors.west),
+                )
+            }
+            false => Edge::Wall,
+        }
+    }
+
+    /// Between the field and its south neighbour, a wall on the last row
+    pub fn south_edge(&self, row: usize, col: usize) -> Edge {
+        match row + 1 < self.0.len() {
+            true => {
+                let (field, south) = (&self.0[row][col], &self.0[row + 1][col]);
+                edge(
+                    (field.paths.south, field.doors.south),
+                    (south.paths.north, south.doors.north),
+                )
+            }
+            false => Edge::Wall,
+        }
+    }
+
+    /// Sets the edge between the field and its east neighbour on both sides, a door is declared
+    /// by the field itself. One-way bits of the edge are cleared, the last column stays a wall
+    pub fn set_east_edge(&mut self, row: usize, col: usize, edge: Edge) {
+        let (near, far) = edge_bits(edge);
+        let field = &mut self.0[row][col];
+        if col + 1 == COLUMN_SIZE {
+            (field.paths.east, field.doors.east, field.one_way.east) = (false, false, false);
+            return;
+        }
+        (field.paths.east, field.doors.east, field.one_way.east) = (near.0, near.1, false);
+        let east = &mut self.0[row][col + 1];
+        (east.paths.west, east.doors.west, east.one_way.west) = (far.0, far.1, false);
+    }
+
+    /// Like [`Labyrinth::set_east_edge`] towards the south neighbour
+    pub fn set_south_edge(&mut self, row: usize, col: usize, edge: Edge) {
+        let (near, far) = edge_bits(edge);
+        let last = row + 1 == self.0.len();
+        let field = &mut self.0[row][col];
+        if last {
+            (field.paths.south, field.doors.south, field.one_way.south) = (false, false, false);
+            return;
+        }
+        (field.paths.south, field.doors.south, field.one_way.south) = (near.0, near.1, false);
+        let south = &mut self.0[row + 1][col];
+        (south.paths.north, south.doors.north, south.one_way.north) = (far.0, far.1, false);
+    }
+
+    pub fn pathfind_matrix(&self) -> (Vec<Vec<u8>>, Vec<bool>, Vec<bool>) {
+        macro_rules! dim {
+            ($row:expr, $col:expr) => {
+                $row * COLUMN_SIZE + $col
+            };
+            (len) => {
+                self.0.len() * COLUMN_SIZE
+            };
+        }
+        let mut path_matrix = vec![vec![0u8; dim![len]]; dim![len]];
+        let mut key_vector = vec![false; dim![len]];
+        let mut end_vector = vec![false; dim![len]];
+        for r in 0..self.0.len() {
+            for c in 0..COLUMN_SIZE {
+                if self.0[r][c].paths.north && r > 0 {
+                    path_matrix[dim![r, c]][dim![r - 1, c]] += 2;
+                    path_matrix[dim![r - 1, c]][dim![r, c]] += 2;
+                }
+                if self.0[r][c].paths.south && r < self.0.len() - 1 {
+                    path_matrix[dim![r, c]][dim![r + 1, c]] += 2;
+                    path_matrix[dim![r + 1, c]][dim![r, c]] += 2;
+                }
+                if self.0[r][c].paths.west && c > 0 {
+                    path_matrix[dim![r, c]][dim![r, c - 1]] += 2;
+                    path_matrix[dim![r, c - 1]][dim![r, c]] += 2;
+                }
+                if self.0[r][c].paths.east && c < COLUMN_SIZE - 1 {
+                    path_matrix[dim![r, c]][dim![r, c + 1]] += 2;
+                    path_matrix[dim![r, c + 1]][dim![r, c]] += 2;
+                }
+                /* */
+                if self.0[r][c].doors.north && r > 0 {
+                    path_matrix[dim![r, c]][dim![r - 1, c]] += 3;
+                    path_matrix[dim![r - 1, c]][dim![r, c]] += 3;
+                }
+                if self.0[r][c].doors.south && r < self.0.len() - 1 {
+                    path_matrix[dim![r, c]][dim![r + 1, c]] += 3;
+                    path_matrix[dim![r + 1, c]][dim![r, c]] += 3;
+                }
+                if self.0[r][c].doors.west && c > 0 {
+                    path_matrix[dim![r, c]][dim![r, c - 1]] += 3;
+                    path_matrix[dim![r, c - 1]][dim![r, c]] += 3;
+                }
+                if self.0[r][c].doors.east && c < COLUMN_SIZE - 1 {
+                    path_matrix[dim![r, c]][dim![r, c + 1]] += 3;
+                    path_matrix[dim![r, c + 1]][dim![r, c]] += 3;
+                }
+                /* */
+                if self.0[r][c].contains_key {
+                    key_vector[dim![r, c]] = true;
+                }
+                if self.0[r][c].is_end {
+                    end_vector[dim![r, c]] = true;
+                }
+            }
+        }
+
+        // graph can be inspected with `dot::to_dot`
+        for row in path_matrix.iter_mut() {
+            for edge in row.iter_mut() {
+                *edge = match edge {
+                    4 => 1,   // path
+                    7 => 255, // door
+                    _ => 0,   // wall
+                };
+            }
+        }
+
+        // one-way passages can't be entered from the neighbour's side
+        for r in 0..self.0.len() {
+            for c in 0..COLUMN_SIZE {
+                if self.0[r][c].one_way.north && r > 0 {
+                    path_matrix[dim![r - 1, c]][dim![r, c]] = 0;
+                }
+                if self.0[r][c].one_way.south && r < self.0.len() - 1 {
+                    path_matrix[dim![r + 1, c]][dim![r, c]] = 0;
+                }
+                if self.0[r][c].one_way.west && c > 0 {
+                    path_matrix[dim![r, c - 1]][dim![r, c]] = 0;
+                }
+                if self.0[r][c].one_way.east && c < COLUMN_SIZE - 1 {
+                    path_matrix[dim![r, c + 1]][dim![r, c]] = 0;
+                }
+            }
+        }
+
+        (path_matrix, key_vector, end_vector)
+    }
+
+    /// A partial walk is the part walked before the limits were hit, it stops short of the end
+    pub fn pathfind(
+        &self,
+        start: usize,
+        end: usize,
+        limits: &Limits,
+    ) -> Outcome<Option<Vec<usize>>> {
+        let (maze, keys, _) = self.pathfind_matrix();
+        let mut stats = pathfinder::SearchStats::default();
+        pathfinder::greedy_pathfind(start, end, &maze, &keys, limits, &mut stats, None)
+    }
+
+    /// Indices of the end fields in reading order
+    pub fn ends(&self) -> Vec<usize> {
+        let fields = self.0.iter().flatten().enumerate();
+        fields.filter(|(_, f)| f.is_end).map(|(i, _)| i).collect()
+    }
+
+    pub fn count_solutions(
+        &self,
+        start: usize,
+        end: usize,
+        limits: &Limits,
+    ) -> Outcome<Option<(usize, usize)>> {
+        let (maze, keys, _) = self.pathfind_matrix();
+        pathfinder::count_optimal_walks(start, end, &maze, &keys, limits)
+    }
+
+    pub fn solutions(
+        &self,
+        start: usize,
+        end: usize,
+        limits: &Limits,
+    ) -> Outcome<pathfinder::OptimalWalks> {
+        let (maze, keys, _) = self.pathfind_matrix();
+        let mut stats = pathfinder::SearchStats::default();
+        pathfinder::optimal_walks(start, end, &maze, &keys, limits, &mut stats, None)
+    }
+
+    pub fn count_simple_solutions(
+        &self,
+        start: usize,
+        end: usize,
+        max_len: usize,
+        limits: &Limits,
+    ) -> Outcome<usize> {
+        let (maze, keys, _) = self.pathfind_matrix();
+        pathfinder::count_walks(start, end, &maze, &keys, max_len, limits)
+    }
+
+    pub fn reachability(
+        &self,
+        start: usize,
+        limits: &Limits,
+    ) -> Outcome<Vec<Option<(usize, usize)>>> {
+        let (maze, keys, _) = self.pathfind_matrix();
+        pathfinder::reachability(start, &maze, &keys, limits)
+    }
+
+    pub fn analyze(&self, start: usize) -> analysis::Report {
+        let (maze, keys, ends) = self.pathfind_matrix();
+        analysis::analyze(start, &maze, &keys, &ends)
+    }
+
+    /// Checks a walk move by move, e.g. one submitted by a player
+    pub fn replay(
+        &self,
+        walk: &[usize],
+        rules: &solver::Rules,
+    ) -> Result<replay::Trace, replay::Violation> {
+        let (maze, keys, ends) = self.pathfind_matrix();
+        replay::replay(walk, rules, &maze, &keys, &ends)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directions_from_str() {
+        let dir = Directions::from_str("1001").unwrap();
+        assert!(dir.west && !dir.east && !dir.north && dir.south);
+        assert!(Directions::from_str("0000").is_ok());
+        assert!(Directions::from_str("10012").is_err());
+        assert!(Directions::from_str("102").is_err());
+        assert!(Directions::from_str("10a1").is_err());
+    }
+
+    #[test]
+    fn field_from_str() {
+        let field = Field::from_str("0101 0100 1100").unwrap();
+        assert!(field.paths.east && field.paths.south && !field.paths.west);
+        assert!(field.doors.east && !field.doors.south);
+        assert!(field.contains_key && !field.is_end);
+        assert!(!field.one_way.south);
+
+        let field = Field::from_str("0001 0000 0011 0001").unwrap();
+        assert!(field.is_end && !field.contains_key);
+        assert!(field.one_way.south && !field.one_way.north);
+
+        assert!(!Field::from_str("0101 0100 1000").unwrap().contains_key);
+        assert!(Field::from_str("0101 0100 110").is_err());
+        assert!(Field::from_str("0101 0100 1100 01").is_err());
+        assert!(Field::from_str("0121 0100 1100").is_err());
+    }
+
+    #[test]
+    fn labyrinth_from_str() {
+        let row = "0000 0000 0000\n".repeat(COLUMN_SIZE);
+        assert_eq!(Labyrinth::from_str(&row.repeat(2)).unwrap().0.len(), 2);
+        assert_eq!(
+            Labyrinth::from_str("0000 0000 0000\n").err().unwrap(),
+            "1 fields don't fill rows of 9"
+        );
+        let err = Labyrinth::from_str(&format!("{row}0000 0000 00"))
+            .err()
+            .unwrap();
+        assert!(err.starts_with("line 10 "), "{err}");
+    }
+
+    #[test]
+    fn fields_and_edges_are_written_back() {
+        for line in ["0101 0100 1100", "0001 0000 0011 0001", "0000 0000 0000"] {
+            assert_eq!(Field::from_str(line).unwrap().to_string(), line);
+        }
+        let mut lab = Labyrinth(vec![vec![Field::default(); COLUMN_SIZE]; 2]);
+        lab.set_east_edge(0, 0, Edge::Door);
+        lab.set_south_edge(0, 1, Edge::Path);
+        lab.set_south_edge(1, 1, Edge::Path); // last row, stays a wall
+        assert_eq!(lab.east_edge(0, 0), Edge::Door);
+        assert_eq!(lab.south_edge(0, 1), Edge::Path);
+        assert!(!lab.0[1][1].paths.south);
+        let lines: Vec<String> = lab.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 2 * COLUMN_SIZE);
+        assert_eq!(lines[0..2], ["0100 0100 0000", "1001 0000 0000"]);
+        lab.set_east_edge(0, 0, Edge::Wall);
+        assert_eq!(lab.east_edge(0, 0), Edge::Wall);
+        assert!(lab.0[0][0].is_empty());
+    }
+}
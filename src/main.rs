@@ -1,328 +1,364 @@
-use std::{error::Error, str::FromStr};
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
 
-use pathfinder::parallel_backtrack;
-const COLUMN_SIZE: usize = 9;
+use lavirint::{
+    display_labyrinth, dot, edit,
+    pathfinder::{self, Limits, Outcome},
+    raster, read_file, render, server,
+    solver::{self, Solver},
+    svg, terminal,
+    theme::{Colors, Theme},
+    visualize, Field, Labyrinth, COLUMN_SIZE, HEAT,
+};
 
-mod pathfinder;
-
-struct Labyrinth(Vec<Vec<Field>>);
-
-#[derive(Debug, Default, Clone, Copy)]
-#[allow(unused)]
-struct Directions<T> {
-    west: T,
-    east: T,
-    north: T,
-    south: T,
-}
-
-#[derive(Debug, Default, Clone, Copy)]
-#[allow(unused)]
-struct Field {
-    paths: Directions<bool>,
-    doors: Directions<bool>,
-    contains_key: bool,
-    is_end: bool,
+fn limits_from_args(args: &[String]) -> Result<Limits, Box<dyn Error>> {
+    match args.iter().position(|a| a == "--timeout") {
+        Some(i) => {
+            let millis: u64 = args
+                .get(i + 1)
+                .ok_or("--timeout needs milliseconds")?
+                .parse()?;
+            Ok(Limits::timeout(Duration::from_millis(millis)))
+        }
+        None => Ok(Limits::default()),
+    }
 }
 
-impl FromStr for Directions<bool> {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        macro_rules! bit_match {
-            ($slice:ident[$index:literal] -> $var:expr) => {
-                match $slice.chars().nth($index) {
-                    Some('0') => $var = false,
-                    Some('1') => $var = true,
-                    _ => return Err(()),
-                }
-            };
-        }
-        if s.len() > 4 {
-            Err(())
-        } else {
-            let mut dir = Self::default();
-            bit_match!(s[0] -> dir.west);
-            bit_match!(s[1] -> dir.east);
-            bit_match!(s[2] -> dir.north);
-            bit_match!(s[3] -> dir.south);
-            Ok(dir)
-        }
+fn parallel_config_from_args(
+    args: &[String],
+) -> Result<pathfinder::ParallelConfig, Box<dyn Error>> {
+    let mut config = pathfinder::ParallelConfig {
+        deterministic: args.iter().any(|a| a == "--deterministic"),
+        ..Default::default()
+    };
+    if let Some(i) = args.iter().position(|a| a == "--threads") {
+        config.threads = args.get(i + 1).ok_or("--threads needs a number")?.parse()?;
     }
+    Ok(config)
 }
 
-fn str_bitwise_and(s: &str) -> bool {
-    !s.is_empty() && s.chars().all(|c| c == '1')
+/// `--color always|never|auto`, auto colors only a terminal and respects NO_COLOR
+fn colors_from_args(args: &[String]) -> Result<Option<Theme>, Box<dyn Error>> {
+    let color = match args.iter().position(|a| a == "--color") {
+        Some(i) => args
+            .get(i + 1)
+            .ok_or("--color needs always, never or auto")?
+            .as_str(),
+        None => "auto",
+    };
+    let enabled = match color {
+        "always" => true,
+        "never" => false,
+        "auto" => terminal::stdout_is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        _ => return Err(format!("--color {color} isn't always, never or auto").into()),
+    };
+    let theme = match args.iter().position(|a| a == "--theme") {
+        Some(i) => args.get(i + 1).ok_or("--theme needs a name")?.parse()?,
+        None => Theme::default(),
+    };
+    Ok(enabled.then_some(theme))
 }
 
-impl FromStr for Field {
-    type Err = ();
+/// The solvers work on the adjacency matrix, a byte for every pair of fields
+const MAX_SOLVE_FIELDS: usize = 10_000;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 14 {
-            return Err(());
-        }
-        Ok(Self {
-            paths: Directions::from_str(&s[0..4])?,
-            doors: Directions::from_str(&s[5..9])?,
-            contains_key: str_bitwise_and(&s[10..12]),
-            is_end: str_bitwise_and(&s[12..14]),
-        })
+/// Shortest walk from 0 to 47 for the pictures
+fn solve_default(l: &Labyrinth, args: &[String]) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+    let fields = l.0.len() * COLUMN_SIZE;
+    if fields > MAX_SOLVE_FIELDS {
+        return Err(format!(
+            "--solution is limited to {MAX_SOLVE_FIELDS} fields, the maze has {fields} and \
+             solving it would take {fields}x{fields} bytes"
+        )
+        .into());
     }
+    let problem = solver::Problem {
+        limits: limits_from_args(args)?,
+        ..Default::default()
+    };
+    Ok(solver::BfsSolver
+        .solve(l, &problem)
+        .outcome
+        .into_inner()
+        .map(|s| s.walk))
 }
 
-fn read_file(path: &str) -> Result<Labyrinth, Box<dyn Error>> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-    let file = File::open(path)?;
-    let reader = BufReader::new(file).lines();
-    let lines: Vec<String> = reader.map(|l| l.expect("Error reading line")).collect();
-    let fields: Vec<Field> = lines
-        .into_iter()
-        .map(|l| Field::from_str(&l).expect("Error parsing line"))
-        .collect();
-    let row_count = fields.len() / COLUMN_SIZE;
-    let mut labyrinth = vec![vec![Field::default(); COLUMN_SIZE]; row_count];
-    for r in 0..row_count {
-        for c in 0..COLUMN_SIZE {
-            labyrinth[r][c] = fields[r * COLUMN_SIZE + c];
-        }
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("edit") {
+        let path = args
+            .get(1)
+            .filter(|a| !a.starts_with("--"))
+            .map_or("labyrinth.txt", String::as_str);
+        let lab = match std::path::Path::new(path).exists() {
+            true => read_file(path)?,
+            false => {
+                let rows = match args.iter().position(|a| a == "--rows") {
+                    Some(i) => args.get(i + 1).ok_or("--rows needs a number")?.parse()?,
+                    None => 6,
+                };
+                Labyrinth(vec![vec![Field::default(); COLUMN_SIZE]; rows.max(1)])
+            }
+        };
+        let start = match args.iter().position(|a| a == "--start") {
+            Some(i) => args.get(i + 1).ok_or("--start needs a field")?.parse()?,
+            None => 0,
+        };
+        let rules = solver::Rules {
+            ignore_doors: args.iter().any(|a| a == "--ignore-doors"),
+        };
+        edit::edit(lab, path, start, rules, colors_from_args(&args)?.as_ref())?;
+        return Ok(());
     }
-    Ok(Labyrinth(labyrinth))
-}
-
-fn display_labyrinth(lab: &Labyrinth) {
-    let fields = &lab.0;
-    #[allow(clippy::needless_range_loop)]
-    for row in 0..fields.len() {
-        for line in 1..=4 {
-            for col in 0..COLUMN_SIZE {
-                if (!fields[row][col].paths.north)
-                    && !fields[row][col].paths.south
-                    && !fields[row][col].paths.east
-                    && !fields[row][col].paths.west
-                    && !fields[row][col].doors.north
-                    && !fields[row][col].doors.south
-                    && !fields[row][col].doors.east
-                    && !fields[row][col].doors.west
-                {
-                    print!("      ");
-                    continue;
-                }
-                match line {
-                    1 => print!(
-                        "┏━{}━┓",
-                        if fields[row][col].doors.north {
-                            "╩╩"
-                        } else if fields[row][col].paths.north {
-                            "┛┗"
-                        } else {
-                            "━━"
-                        }
-                    ),
-                    2 => print!(
-                        "{} {} {}",
-                        if fields[row][col].doors.west {
-                            "╣"
-                        } else if fields[row][col].paths.west {
-                            "┛"
-                        } else {
-                            "┃"
-                        },
-                        if fields[row][col].is_end {
-                            "🚩"
-                        } else if fields[row][col].contains_key {
-                            "🗝️ "
-                        } else {
-                            "  "
-                        },
-                        if fields[row][col].doors.east {
-                            "╠"
-                        } else if fields[row][col].paths.east {
-                            "┗"
-                        } else {
-                            "┃"
-                        },
-                    ),
-                    3 => print!(
-                        "{} {:2} {}",
-                        if fields[row][col].doors.west {
-                            "╣"
-                        } else if fields[row][col].paths.west {
-                            "┓"
-                        } else {
-                            "┃"
-                        },
-                        row * COLUMN_SIZE + col,
-                        if fields[row][col].doors.east {
-                            "╠"
-                        } else if fields[row][col].paths.east {
-                            "┏"
-                        } else {
-                            "┃"
-                        },
-                    ),
-                    4 => print!(
-                        "┗━{}━┛",
-                        if fields[row][col].doors.south {
-                            "╦╦"
-                        } else if fields[row][col].paths.south {
-                            "┓┏"
-                        } else {
-                            "━━"
-                        }
-                    ),
-                    _ => {}
-                }
+    if args.first().map(String::as_str) == Some("serve") {
+        let mut config = server::Config::default();
+        let value = |flag: &str| -> Result<Option<&String>, Box<dyn Error>> {
+            match args.iter().position(|a| a == flag) {
+                Some(i) => Ok(Some(
+                    args.get(i + 1).ok_or(format!("{flag} needs a value"))?,
+                )),
+                None => Ok(None),
             }
-            println!()
+        };
+        if let Some(host) = value("--host")? {
+            config.host = host.clone();
+        }
+        if let Some(port) = value("--port")? {
+            config.port = port.parse()?;
+        }
+        if let Some(workers) = value("--workers")? {
+            config.workers = workers.parse()?;
+        }
+        if let Some(bytes) = value("--max-body")? {
+            config.max_body = bytes.parse()?;
         }
+        if let Some(fields) = value("--max-fields")? {
+            config.max_fields = fields.parse()?;
+        }
+        if let Some(nodes) = value("--max-nodes")? {
+            config.max_nodes = nodes.parse()?;
+        }
+        if let Some(millis) = value("--timeout")? {
+            config.timeout = Duration::from_millis(millis.parse()?);
+        }
+        server::serve(config)?;
+        return Ok(());
     }
-}
-
-impl Labyrinth {
-    fn pathfind_matrix(&self) -> (Vec<Vec<u8>>, Vec<bool>, Vec<bool>) {
-        macro_rules! dim {
-            ($row:expr, $col:expr) => {
-                $row * COLUMN_SIZE + $col
-            };
-            (len) => {
-                self.0.len() * COLUMN_SIZE
-            };
+    let l = read_file("./labyrinth.txt")?;
+    if args.first().map(String::as_str) == Some("analyze") {
+        let report = l.analyze(0);
+        match args.iter().any(|a| a == "--json") {
+            true => println!("{}", report.to_json()),
+            false => print!("{report}"),
         }
-        let mut path_matrix = vec![vec![0u8; dim![len]]; dim![len]];
-        let mut key_vector = vec![false; dim![len]];
-        let mut end_vector = vec![false; dim![len]];
-        for r in 0..self.0.len() {
-            for c in 0..COLUMN_SIZE {
-                if self.0[r][c].paths.north && r > 0 {
-                    path_matrix[dim![r, c]][dim![r - 1, c]] += 2;
-                    path_matrix[dim![r - 1, c]][dim![r, c]] += 2;
-                }
-                if self.0[r][c].paths.south && r < self.0.len() - 1 {
-                    path_matrix[dim![r, c]][dim![r + 1, c]] += 2;
-                    path_matrix[dim![r + 1, c]][dim![r, c]] += 2;
-                }
-                if self.0[r][c].paths.west && c > 0 {
-                    path_matrix[dim![r, c]][dim![r, c - 1]] += 2;
-                    path_matrix[dim![r, c - 1]][dim![r, c]] += 2;
-                }
-                if self.0[r][c].paths.east && c < COLUMN_SIZE - 1 {
-                    path_matrix[dim![r, c]][dim![r, c + 1]] += 2;
-                    path_matrix[dim![r, c + 1]][dim![r, c]] += 2;
-                }
-                /* */
-                if self.0[r][c].doors.north && r > 0 {
-                    path_matrix[dim![r, c]][dim![r - 1, c]] += 3;
-                    path_matrix[dim![r - 1, c]][dim![r, c]] += 3;
-                }
-                if self.0[r][c].doors.south && r < self.0.len() - 1 {
-                    path_matrix[dim![r, c]][dim![r + 1, c]] += 3;
-                    path_matrix[dim![r + 1, c]][dim![r, c]] += 3;
-                }
-                if self.0[r][c].doors.west && c > 0 {
-                    path_matrix[dim![r, c]][dim![r, c - 1]] += 3;
-                    path_matrix[dim![r, c - 1]][dim![r, c]] += 3;
-                }
-                if self.0[r][c].doors.east && c < COLUMN_SIZE - 1 {
-                    path_matrix[dim![r, c]][dim![r, c + 1]] += 3;
-                    path_matrix[dim![r, c + 1]][dim![r, c]] += 3;
-                }
-                /* */
-                if self.0[r][c].contains_key {
-                    key_vector[dim![r, c]] = true;
-                }
-                if self.0[r][c].is_end {
-                    end_vector[dim![r, c]] = true;
-                }
-            }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("reach") {
+        let start = match args.get(1).filter(|a| !a.starts_with("--")) {
+            Some(start) => start.parse()?,
+            None => 0,
+        };
+        let reach = l.reachability(start, &limits_from_args(&args)?);
+        let partial = reach.is_partial();
+        display_labyrinth(&l, Some(&reach.into_inner()));
+        println!(
+            "keys spent: {}=0 {}=1 {}=2 {}=3+ ╳╳=unreachable, keys and ends keep their marker",
+            HEAT[0], HEAT[1], HEAT[2], HEAT[3]
+        );
+        if partial {
+            println!("stopped early, fields not reached yet show as unreachable");
         }
-
-        // print!["   "];
-        // for i in 0..dim![len] {
-        //     print!["{:2} ", i + 1];
-        // }
-        // println![];
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..dim![len] {
-            // print!["{:2} ", i + 1];
-            for j in 0..dim![len] {
-                path_matrix[i][j] = match path_matrix[i][j] {
-                    4 => 1,   // path
-                    7 => 255, // door
-                    _ => 0,   // wall
-                };
-                // print![
-                //     " {} ",
-                //     match path_matrix[i][j] {
-                //         1 => {
-                //             "#"
-                //         }
-                //         255 => {
-                //             "D"
-                //         }
-                //         _ => {
-                //             "·"
-                //         }
-                //     }
-                // ];
-            }
-            // println![];
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("play") {
+        let start = match args.get(1).filter(|a| !a.starts_with("--")) {
+            Some(start) => start.parse()?,
+            None => 0,
+        };
+        let rules = solver::Rules {
+            ignore_doors: args.iter().any(|a| a == "--ignore-doors"),
+        };
+        lavirint::play::play(&l, start, &rules)?;
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("visualize") {
+        let algorithm = args
+            .get(1)
+            .filter(|a| !a.starts_with("--"))
+            .map_or("a_star", String::as_str);
+        let delay = match args.iter().position(|a| a == "--delay") {
+            Some(i) => args
+                .get(i + 1)
+                .ok_or("--delay needs milliseconds")?
+                .parse()?,
+            None => 200,
+        };
+        let end = *l.ends().first().ok_or("the maze has no end field")?;
+        let frames = visualize::record(&l, algorithm, 0, end, &parallel_config_from_args(&args)?)
+            .ok_or(format!(
+            "unknown algorithm {algorithm}, try one of {:?}",
+            visualize::ALGORITHMS
+        ))?;
+        visualize::animate(&l, &frames, Duration::from_millis(delay))?;
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("raster") {
+        let path = args
+            .get(1)
+            .filter(|a| !a.starts_with("--"))
+            .map_or("labyrinth.png", String::as_str);
+        let mut scale = raster::Scale::default();
+        if let Some(i) = args.iter().position(|a| a == "--cell") {
+            scale.cell = args.get(i + 1).ok_or("--cell needs pixels")?.parse()?;
         }
-
-        (path_matrix, key_vector, end_vector)
+        if let Some(i) = args.iter().position(|a| a == "--wall") {
+            scale.wall = args.get(i + 1).ok_or("--wall needs pixels")?.parse()?;
+        }
+        let walk = match args.iter().any(|a| a == "--solution") {
+            true => solve_default(&l, &args)?,
+            false => None,
+        };
+        let image = raster::render(&l, walk.as_deref(), scale);
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        match path.ends_with(".ppm") {
+            true => image.write_ppm(&mut file)?,
+            false => image.write_png(&mut file)?,
+        }
+        return Ok(());
     }
-
-    fn pathfind(&self, start: usize, end: usize) -> Option<Vec<usize>> {
-        let (mut maze, mut keys, _) = self.pathfind_matrix();
-        let mut whole_path: Vec<usize> = vec![];
-        let mut key_inventory = 0isize;
-
-        let mut start = start;
-        loop {
-            let (ideal_path, consumed) = pathfinder::a_star(start, end, &maze)?;
-            let cumsum = pathfinder::key_cumsum(&ideal_path, &consumed, &keys);
-            let (pickup_path, kinv) =
-                pathfinder::key_pickup(&ideal_path, &cumsum, &mut maze, &mut keys, key_inventory)?;
-            key_inventory = kinv;
-            if pickup_path.is_empty() {
-                whole_path.extend(ideal_path);
-                break;
-            } else {
-                start = pickup_path[pickup_path.len() - 1];
-                whole_path.extend(pickup_path);
+    if args.first().map(String::as_str) == Some("dot") {
+        let walk = match args.iter().any(|a| a == "--solution") {
+            true => solve_default(&l, &args)?,
+            false => None,
+        };
+        let dot = dot::to_dot(&l, walk.as_deref());
+        match args.get(1).filter(|a| !a.starts_with("--")) {
+            Some(path) => std::fs::write(path, dot)?,
+            None => print!("{dot}"),
+        }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("svg") {
+        let path = args
+            .get(1)
+            .filter(|a| !a.starts_with("--"))
+            .map_or("labyrinth.svg", String::as_str);
+        let walk = match args.iter().any(|a| a == "--solution") {
+            true => solve_default(&l, &args)?,
+            false => None,
+        };
+        std::fs::write(path, svg::to_svg(&l, walk.as_deref()))?;
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("replay") {
+        let walk = args[1..]
+            .iter()
+            .filter(|a| !a.starts_with("--"))
+            .map(|a| a.parse())
+            .collect::<Result<Vec<usize>, _>>()?;
+        let rules = solver::Rules {
+            ignore_doors: args.iter().any(|a| a == "--ignore-doors"),
+        };
+        let json = args.iter().any(|a| a == "--json");
+        match (l.replay(&walk, &rules), json) {
+            (Ok(trace), true) => println!("{{\"valid\":true,\"trace\":{}}}", trace.to_json()),
+            (Ok(trace), false) => print!("{trace}"),
+            (Err(violation), true) => {
+                println!("{{\"valid\":false,\"violation\":\"{violation}\"}}")
             }
+            (Err(violation), false) => println!("invalid walk, {violation}"),
         }
-        Some(pathfinder::deduplicate_path(&whole_path))
+        return Ok(());
     }
-
-    #[allow(unused)]
-    fn pathfind_parallel(&self, start: usize, end: usize) -> Option<Vec<usize>> {
-        let (mut maze, mut keys, _) = self.pathfind_matrix();
-        let mut start = start;
-        pathfinder::parallel_backtrack(start, end, &maze, &keys);
-        None
+    let json = args.iter().any(|a| a == "--json");
+    if !json {
+        let style = match args.iter().position(|a| a == "--style") {
+            Some(i) => args.get(i + 1).ok_or("--style needs a name")?.as_str(),
+            None => "box",
+        };
+        let indices = !args.iter().any(|a| a == "--no-indices");
+        let walk = match args.iter().any(|a| a == "--solution") {
+            true => solve_default(&l, &args)?.unwrap_or_default(),
+            false => vec![],
+        };
+        let colors = colors_from_args(&args)?.map(|theme| Colors { theme, walk: &walk });
+        let renderer = render::by_name(style, indices, colors).ok_or(format!(
+            "unknown style {style}, try one of {:?}",
+            render::NAMES
+        ))?;
+        renderer.render(&l, &mut std::io::stdout().lock())?;
     }
-}
 
-use std::time::Instant;
-fn main() -> Result<(), Box<dyn Error>> {
-    let l = read_file("./labyrinth.txt")?;
-    display_labyrinth(&l);
-    let now = Instant::now();
-
-    let pp = l.pathfind_parallel(0, 47);
-
-    let elapsed = now.elapsed();
-    println!("Time for parallel exec {:?}", elapsed);
-    let now = Instant::now();
-
-    let p = l.pathfind(0, 47);
-    let elapsed = now.elapsed();
-
-    println!("Time for normal exec {:?}", elapsed);
+    let mut names: Vec<&str> = args
+        .windows(2)
+        .filter(|w| w[0] == "--solver")
+        .map(|w| w[1].as_str())
+        .collect();
+    if names.is_empty() {
+        names = vec!["parallel", "greedy"];
+    }
+    for name in names {
+        let solver = solver::by_name(name, &parallel_config_from_args(&args)?).ok_or(format!(
+            "unknown solver {name}, try one of {:?}",
+            solver::NAMES
+        ))?;
+        let problem = solver::Problem {
+            start: 0,
+            goals: vec![], // every end field
+            rules: solver::Rules {
+                ignore_doors: args.iter().any(|a| a == "--ignore-doors"),
+            },
+            limits: limits_from_args(&args)?,
+        };
+        let now = Instant::now();
+        let report = solver.solve(&l, &problem);
+        if json {
+            println!("{}", report.to_json());
+            continue;
+        }
+        println!("Time for {} exec {:?}", solver.name(), now.elapsed());
+        match &report.outcome {
+            Outcome::Complete(Some(solution)) => println!("{solution}"),
+            Outcome::Partial(Some(solution)) => println!("partial {solution}"),
+            Outcome::Complete(None) => println!("no solution"),
+            Outcome::Partial(None) => println!("no solution found in time"),
+        }
+        if args.iter().any(|a| a == "--stats") {
+            print!("{}", report.stats);
+        }
+    }
 
-    println!["{:?}", p.unwrap()];
-    println!["{pp:?}"];
+    if json {
+        return Ok(());
+    }
+    // there can be exponentially many
+    let shown = match args.iter().any(|a| a == "--all-walks") {
+        true => usize::MAX,
+        false => match args.iter().position(|a| a == "--walks") {
+            Some(i) => args.get(i + 1).ok_or("--walks needs a number")?.parse()?,
+            None => 10,
+        },
+    };
+    for end in l.ends() {
+        let Outcome::Complete(Some((length, count))) =
+            l.count_solutions(0, end, &limits_from_args(&args)?)
+        else {
+            continue;
+        };
+        println!("{count} optimal walk(s) of length {length} to {end}");
+        for walk in l
+            .solutions(0, end, &Limits::default())
+            .into_inner()
+            .take(shown)
+        {
+            println!["{walk:?}"];
+        }
+        if count > shown {
+            println!("and {} more, --all-walks prints them", count - shown);
+        }
+    }
 
     Ok(())
 }
@@ -1,11 +1,159 @@
-use super::COLUMN_SIZE;
+use super::{clock::Instant, COLUMN_SIZE};
 use std::{
     cmp::Ordering,
-    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
-    sync::{Arc, Mutex, RwLock},
-    thread, vec,
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::{self, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+    vec,
 };
 
+/// Shared flag a caller flips to stop a running search
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    #[allow(unused)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    #[allow(unused)]
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed)
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// Nodes a search may still expand, shared by every worker and goal of the search like a
+/// `CancelToken`. Memory grows with the nodes expanded, so this bounds it where a deadline can't
+#[derive(Debug, Clone)]
+pub struct Budget(Arc<AtomicUsize>);
+
+impl Budget {
+    pub fn new(nodes: usize) -> Self {
+        Budget(Arc::new(AtomicUsize::new(nodes)))
+    }
+    /// Takes one node, false once there are none left
+    fn spend(&self) -> bool {
+        self.0
+            .fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |n| {
+                n.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Limits {
+    pub deadline: Option<Instant>,
+    pub cancel: Option<CancelToken>,
+    pub budget: Option<Budget>,
+}
+
+impl Limits {
+    #[allow(unused)]
+    pub fn timeout(timeout: Duration) -> Self {
+        Limits {
+            deadline: Some(Instant::now() + timeout),
+            ..Default::default()
+        }
+    }
+    /// Searches ask before expanding each node, so every call spends a node of the budget
+    pub fn exceeded(&self) -> bool {
+        self.deadline.map_or(false, |d| Instant::now() >= d)
+            || self.cancel.as_ref().map_or(false, |c| c.is_cancelled())
+            || self.budget.as_ref().map_or(false, |b| !b.spend())
+    }
+}
+
+/// Result of a search, `Partial` holds the best found before the limits were hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<T> {
+    Complete(T),
+    Partial(T),
+}
+
+impl<T> Outcome<T> {
+    #[allow(unused)]
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Outcome::Partial(_))
+    }
+    pub fn into_inner(self) -> T {
+        match self {
+            Outcome::Complete(t) | Outcome::Partial(t) => t,
+        }
+    }
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U> {
+        match self {
+            Outcome::Complete(t) => Outcome::Complete(f(t)),
+            Outcome::Partial(t) => Outcome::Partial(f(t)),
+        }
+    }
+}
+
+/// Work done by a search
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchStats {
+    pub expanded: usize,         // nodes whose successors were generated
+    pub pruned: usize,           // successors dropped without being expanded
+    pub peak_open: usize,        // largest the open set or queue got
+    pub keys_considered: usize,  // keys picked up or weighed for a detour
+    pub doors_considered: usize, // door edges walked through or tried
+    pub per_thread: Vec<usize>,  // nodes expanded by each parallel worker
+}
+
+/// What changed when a search expanded a node, playing the frames in order rebuilds the
+/// search. The open set is what was pushed minus what was expanded or dropped
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub current: Vec<usize>, // walk to the node being expanded, empty when none was
+    pub pushed: Vec<usize>,  // fields of the nodes it added to the open set or queue
+    pub dropped: Vec<usize>, // fields of open nodes thrown away without being expanded
+    pub best: Option<Vec<usize>>, // best walk to the end, when it just improved
+}
+
+/// Watches a search, gets a frame for every node expanded in the order they were expanded
+pub trait Recorder {
+    fn record(&mut self, frame: Frame);
+}
+
+impl Recorder for Vec<Frame> {
+    fn record(&mut self, frame: Frame) {
+        self.push(frame);
+    }
+}
+
+/// Only builds the frame when recording
+fn record(recorder: &mut Option<&mut dyn Recorder>, frame: impl FnOnce() -> Frame) {
+    if let Some(recorder) = recorder {
+        recorder.record(frame());
+    }
+}
+
+impl SearchStats {
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.expanded += other.expanded;
+        self.pruned += other.pruned;
+        self.peak_open = self.peak_open.max(other.peak_open);
+        self.keys_considered += other.keys_considered;
+        self.doors_considered += other.doors_considered;
+        // the same workers run once per goal, their counts add up
+        for (i, expanded) in other.per_thread.iter().enumerate() {
+            match self.per_thread.get_mut(i) {
+                Some(total) => *total += expanded,
+                None => self.per_thread.push(*expanded),
+            }
+        }
+    }
+}
+
 fn heuristic(from: usize, to: usize) -> usize {
     let dist = from.abs_diff(to);
     let (r, c) = (dist / COLUMN_SIZE, dist % COLUMN_SIZE);
@@ -43,7 +191,14 @@ pub fn deduplicate_path(path: &[usize]) -> Vec<usize> {
 }
 
 #[allow(unused)]
-pub fn a_star(start: usize, end: usize, graph: &[Vec<u8>]) -> Option<(Vec<usize>, Vec<bool>)> {
+pub fn a_star(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    limits: &Limits,
+    stats: &mut SearchStats,
+    mut recorder: Option<&mut dyn Recorder>,
+) -> Outcome<Option<(Vec<usize>, Vec<bool>)>> {
     let mut graph = graph.to_owned();
 
     let mut open_set = BinaryHeap::<usize>::with_capacity(graph.len());
@@ -59,19 +214,34 @@ pub fn a_star(start: usize, end: usize, graph: &[Vec<u8>]) -> Option<(Vec<usize>
     let mut global_key_util = vec![usize::MAX; graph.len()];
     global_key_util[start] = 0;
 
+    let mut closest = start;
     while let Some(current) = open_set.pop() {
         if current == end {
-            return Some((traverse_path(&came_from, end), consumed_key));
+            return Outcome::Complete(Some((traverse_path(&came_from, end), consumed_key)));
         }
+        if heuristic(current, end) < heuristic(closest, end) {
+            closest = current;
+        }
+        if limits.exceeded() {
+            return Outcome::Partial(Some((traverse_path(&came_from, closest), consumed_key)));
+        }
+        stats.expanded += 1;
+        let walk = recorder
+            .is_some()
+            .then(|| traverse_path(&came_from, current));
+        let mut pushed = vec![];
         for neighbour in 0..graph[current].len() {
             let tentative_score = global_score[current] + 1;
             let mut tentative_keys = global_key_util[current];
             if graph[current][neighbour] == 0 {
                 continue;
             } else if graph[current][neighbour] == 255 {
+                stats.doors_considered += 1;
                 tentative_keys += 1;
                 graph[current][neighbour] = 1;
-                graph[neighbour][current] = 1;
+                if graph[neighbour][current] == 255 {
+                    graph[neighbour][current] = 1;
+                }
                 consumed_key[neighbour] = true;
                 // println!("consumed key {current}->{neighbour}");
             }
@@ -84,87 +254,280 @@ pub fn a_star(start: usize, end: usize, graph: &[Vec<u8>]) -> Option<(Vec<usize>
                 finish_score[neighbour] = tentative_score + heuristic(neighbour, end);
                 if open_set.iter().all(|e| *e != neighbour) {
                     open_set.push(neighbour);
+                    pushed.push(neighbour);
+                    stats.peak_open = stats.peak_open.max(open_set.len());
                 }
+            } else {
+                stats.pruned += 1;
             }
         }
+        record(&mut recorder, || Frame {
+            current: walk.unwrap_or_default(),
+            pushed,
+            ..Default::default()
+        });
     }
-    None
+    Outcome::Complete(None)
 }
 
-#[derive(Debug, Default, Clone)]
+/// A* to the end, detouring to the closest keys whenever the walk runs out of them.
+/// A partial walk is made of the detours finished before the limits were hit
+pub fn greedy_pathfind(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    limits: &Limits,
+    stats: &mut SearchStats,
+    mut recorder: Option<&mut dyn Recorder>,
+) -> Outcome<Option<Vec<usize>>> {
+    let (mut maze, mut keys) = (graph.to_owned(), keys.to_owned());
+    let mut whole_path: Vec<usize> = vec![];
+    let mut key_inventory = 0isize;
+
+    let mut start = start;
+    loop {
+        let (ideal_path, consumed) = match a_star(
+            start,
+            end,
+            &maze,
+            limits,
+            stats,
+            recorder.as_mut().map(|r| &mut **r as _),
+        ) {
+            Outcome::Complete(Some(found)) => found,
+            Outcome::Complete(None) => return Outcome::Complete(None),
+            // a* opens doors for free, only the legs already walked are kept
+            Outcome::Partial(_) => {
+                return Outcome::Partial(
+                    (!whole_path.is_empty()).then(|| deduplicate_path(&whole_path)),
+                );
+            }
+        };
+        let cumsum = key_cumsum(&ideal_path, &consumed, &keys);
+        let (pickup_path, kinv) = match key_pickup(
+            &ideal_path,
+            &cumsum,
+            &mut maze,
+            &mut keys,
+            key_inventory,
+            stats,
+        ) {
+            Some(pickup) => pickup,
+            None => return Outcome::Complete(None),
+        };
+        key_inventory = kinv;
+        record(&mut recorder, || Frame {
+            current: deduplicate_path(&[&whole_path[..], &pickup_path[..]].concat()),
+            ..Default::default()
+        });
+        if pickup_path.is_empty() {
+            whole_path.extend(ideal_path);
+            break;
+        } else {
+            start = pickup_path[pickup_path.len() - 1];
+            whole_path.extend(pickup_path);
+        }
+        if limits.exceeded() {
+            return Outcome::Partial(Some(deduplicate_path(&whole_path)));
+        }
+    }
+    Outcome::Complete(Some(deduplicate_path(&whole_path)))
+}
+
+/// Fixed size set of indices shared between states until one of them changes it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Bits(Arc<Vec<u64>>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Bits(Arc::new(vec![0; (len + 63) / 64]))
+    }
+    fn contains(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+    fn insert(&mut self, i: usize) {
+        Arc::make_mut(&mut self.0)[i / 64] |= 1 << (i % 64)
+    }
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+/// Graph and keys every state of one search points to, doors are numbered for `Bits`
+#[derive(Debug)]
+struct Maze {
+    graph: Vec<Vec<u8>>,
+    keys: Vec<bool>,
+    door_ids: HashMap<(usize, usize), usize>,
+}
+
+/// One field of a walk, walks branching off the same prefix share it
+#[derive(Debug)]
+struct Step {
+    field: usize,
+    previous: Option<Arc<Step>>,
+}
+
+impl Drop for Step {
+    // unlink long walks one step at a time instead of recursing through them
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(step) = previous {
+            match Arc::try_unwrap(step) {
+                Ok(mut step) => previous = step.previous.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+fn walk_to(last: &Arc<Step>) -> Vec<usize> {
+    let mut walk = vec![];
+    let mut step = Some(last);
+    while let Some(s) = step {
+        walk.push(s.field);
+        step = s.previous.as_ref();
+    }
+    walk.reverse();
+    walk
+}
+
+#[derive(Debug, Clone)]
 pub struct State {
-    walk: Vec<usize>, // walk[-1] -> last visited
-    doors_opened: HashSet<(usize, usize)>,
-    keys_pickedup: Vec<bool>,
-    graph: Arc<Vec<Vec<u8>>>,
-    keys: Arc<Vec<bool>>,
+    last: Arc<Step>, // last visited, the walk is rebuilt by following `previous`
+    len: usize,
+    keys_in_hand: usize,
+    doors_opened: Bits,
+    keys_pickedup: Bits,
+    maze: Arc<Maze>,
 }
 
 impl PartialOrd for State {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.walk.len().cmp(&other.walk.len()))
+        Some(self.len.cmp(&other.len))
     }
 }
 
 impl PartialEq for State {
     fn eq(&self, other: &Self) -> bool {
-        self.walk.len() == other.walk.len()
+        self.len == other.len
     }
 }
 
-impl State {
-    fn key_count(&self) -> usize {
-        self.keys_pickedup.iter().filter(|e| **e).count() - self.doors_opened.len()
+type StateKey = (usize, Bits, Bits);
+
+fn node_pair(from: usize, to: usize) -> (usize, usize) {
+    if from > to {
+        (to, from)
+    } else {
+        (from, to)
     }
-    fn node_pair(&self, to: usize) -> (usize, usize) {
-        let from = self.walk[self.walk.len() - 1];
-        if from > to {
-            (to, from)
-        } else {
-            (from, to)
+}
+
+impl State {
+    fn new(start: usize, graph: &[Vec<u8>], keys: &[bool]) -> Self {
+        let mut door_ids = HashMap::new();
+        for (from, row) in graph.iter().enumerate() {
+            for (to, edge) in row.iter().enumerate() {
+                if *edge == 255 {
+                    let next_id = door_ids.len();
+                    door_ids.entry(node_pair(from, to)).or_insert(next_id);
+                }
+            }
         }
+        State {
+            last: Arc::new(Step {
+                field: start,
+                previous: None,
+            }),
+            len: 1,
+            keys_in_hand: 0,
+            doors_opened: Bits::new(door_ids.len()),
+            keys_pickedup: Bits::new(graph.len()),
+            maze: Arc::new(Maze {
+                graph: graph.to_owned(),
+                keys: keys.to_owned(),
+                door_ids,
+            }),
+        }
+    }
+    fn position(&self) -> usize {
+        self.last.field
+    }
+    fn to_walk(&self) -> Vec<usize> {
+        walk_to(&self.last)
+    }
+    /// Position and inventory, two walks with the same key can continue the same way
+    fn key(&self) -> StateKey {
+        (
+            self.position(),
+            self.keys_pickedup.clone(),
+            self.doors_opened.clone(),
+        )
+    }
+    /// Compact stand-in for `key`
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.key().hash(&mut hasher);
+        hasher.finish()
+    }
+    fn door_id(&self, to: usize) -> usize {
+        self.maze.door_ids[&node_pair(self.position(), to)]
     }
     fn door_opened(&self, to: usize) -> bool {
-        self.doors_opened.contains(&self.node_pair(to))
+        self.doors_opened.contains(self.door_id(to))
+    }
+    fn step(&self, to: usize) -> Self {
+        let mut next = self.clone();
+        next.last = Arc::new(Step {
+            field: to,
+            previous: Some(self.last.clone()),
+        });
+        next.len += 1;
+        if self.maze.keys[to] && !self.keys_pickedup.contains(to) {
+            next.keys_pickedup.insert(to);
+            next.keys_in_hand += 1;
+        }
+        next
     }
-    fn open_door(mut self, to: usize) -> Option<Self> {
-        match self.key_count() > 0 {
+    fn open_door(&self, to: usize) -> Option<Self> {
+        match self.keys_in_hand > 0 {
             true => {
-                self.doors_opened.insert(self.node_pair(to));
-                self.walk.push(to);
-                if self.keys[to] && !self.keys_pickedup[to] {
-                    self.keys_pickedup[to] = true
-                }
-                Some(self)
+                let mut next = self.step(to);
+                next.doors_opened.insert(self.door_id(to));
+                next.keys_in_hand -= 1;
+                Some(next)
             }
             false => None,
         }
     }
-    fn walk(mut self, to: usize) -> Option<Self> {
-        let from = self.walk[self.walk.len() - 1];
-        match self.graph[from][to] {
-            1 => {
-                self.walk.push(to);
-                if self.keys[to] && !self.keys_pickedup[to] {
-                    self.keys_pickedup[to] = true
-                }
-                return Some(self);
-            }
+    fn walk(&self, to: usize) -> Option<Self> {
+        match self.maze.graph[self.position()][to] {
+            1 => Some(self.step(to)),
+            255 if self.door_opened(to) => Some(self.step(to)),
             255 => self.open_door(to),
             _ => None,
         }
     }
+    /// Two steps back to the same field, pointless unless the field between had a key
+    fn bounced(&self) -> bool {
+        match &self.last.previous {
+            Some(middle) => match &middle.previous {
+                Some(before) => before.field == self.last.field && !self.maze.keys[middle.field],
+                None => false,
+            },
+            None => false,
+        }
+    }
     fn next_states(&self) -> Vec<Self> {
-        let from = self.walk[self.walk.len() - 1];
+        let from = self.position();
         let mut v = Vec::with_capacity(4);
-        for next_field in 0..self.graph[from].len() {
-            if self.graph[from][next_field] != 0 {
-                if let Some(n) = self.clone().walk(next_field) {
-                    if n.walk.len() >= 3 {
-                        let l = n.walk.len();
-                        if n.walk[l - 1] == n.walk[l - 3] && !self.keys[n.walk[l - 2]] {
-                            continue;
-                        }
+        for next_field in 0..self.maze.graph[from].len() {
+            if self.maze.graph[from][next_field] != 0 {
+                if let Some(n) = self.walk(next_field) {
+                    if n.bounced() {
+                        continue;
                     }
                     v.push(n)
                 }
@@ -173,109 +536,560 @@ impl State {
         v
     }
     fn at_end(&self, end: usize) -> bool {
-        self.walk[self.walk.len() - 1] == end
+        self.position() == end
+    }
+    fn tally(&self, next_states: &[State], stats: &mut SearchStats) {
+        stats.expanded += 1;
+        for next in next_states {
+            if self.maze.graph[self.position()][next.position()] == 255 {
+                stats.doors_considered += 1;
+            }
+            if next.keys_in_hand > self.keys_in_hand {
+                stats.keys_considered += 1;
+            }
+        }
     }
 }
 
-pub fn worker(
+const VISITED_SHARDS: usize = 64;
+/// How long an idle worker sleeps before it checks the limits again
+const PARK_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// State in a worker's open set, ordered so the heap pops the lowest bound first
+/// and prefers the longer walk among equal bounds
+struct Bounded {
+    bound: usize,
+    state: State,
+}
+
+impl Ord for Bounded {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .bound
+            .cmp(&self.bound)
+            .then_with(|| self.state.len.cmp(&other.state.len))
+    }
+}
+
+impl PartialOrd for Bounded {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Bounded {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Bounded {}
+
+#[derive(Debug, Clone)]
+pub struct ParallelConfig {
+    pub threads: usize,
+    /// Return the lexicographically smallest optimal walk, the same for any thread count
+    pub deterministic: bool,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        ParallelConfig {
+            threads: default_threads(),
+            deterministic: false,
+        }
+    }
+}
+
+/// Shortest walk reaching a state, deterministic searches also keep the walk to break ties
+type Visit = (usize, Option<Arc<Step>>);
+
+/// Work shared by the parallel workers, every worker owns an open set and steals from the others
+struct Scheduler {
+    open_sets: Vec<Mutex<BinaryHeap<Bounded>>>,
+    pending: AtomicUsize, // states pushed but not yet expanded
+    parked: AtomicUsize,  // workers waiting for states
+    idle: Mutex<()>,
+    wake: Condvar, // signalled when states are pushed or the last one is done
+    min_walk: Mutex<Option<Vec<usize>>>,
+    visited: Vec<Mutex<HashMap<u64, Visit>>>, // state hash -> shortest walk reaching it
+    remaining: Vec<Option<usize>>,            // distance to the end with all doors open
+    deterministic: bool,
+}
+
+impl Scheduler {
+    fn new(config: &ParallelConfig, initial_state: State, remaining: Vec<Option<usize>>) -> Self {
+        let open_sets: Vec<_> = (0..config.threads.max(1))
+            .map(|_| Mutex::new(BinaryHeap::new()))
+            .collect();
+        let visited: Vec<_> = (0..VISITED_SHARDS)
+            .map(|_| Mutex::new(HashMap::new()))
+            .collect();
+        let scheduler = Scheduler {
+            open_sets,
+            pending: AtomicUsize::new(0),
+            parked: AtomicUsize::new(0),
+            idle: Mutex::new(()),
+            wake: Condvar::new(),
+            min_walk: Mutex::new(None),
+            visited,
+            remaining,
+            deterministic: config.deterministic,
+        };
+        scheduler.first_visit(&initial_state);
+        scheduler.push(0, vec![initial_state]);
+        scheduler
+    }
+    /// Admissible length of the shortest walk to the end continuing this one,
+    /// `None` if the end can't be reached from here at all
+    fn lower_bound(&self, state: &State) -> Option<usize> {
+        self.remaining[state.position()].map(|r| state.len + r)
+    }
+    /// False if the same field with the same keys and doors was reached by a walk as short,
+    /// in deterministic mode an equally long walk has to be lexicographically smaller too
+    fn first_visit(&self, state: &State) -> bool {
+        let hash = state.state_hash();
+        let mut shard = self.visited[hash as usize % VISITED_SHARDS].lock().unwrap();
+        let seen = match shard.get(&hash) {
+            Some((len, _)) if *len < state.len => true,
+            Some((len, Some(last))) if *len == state.len => walk_to(last) <= state.to_walk(),
+            Some((len, None)) if *len == state.len => true,
+            _ => false,
+        };
+        if !seen {
+            let last = self.deterministic.then(|| state.last.clone());
+            shard.insert(hash, (state.len, last));
+        }
+        !seen
+    }
+    /// Whether a walk with this lower bound can still be better than the best one,
+    /// equally long walks are kept in deterministic mode as they may be smaller
+    fn promising(&self, bound: usize) -> bool {
+        match self.deterministic {
+            true => bound <= self.bound(),
+            false => bound < self.bound(),
+        }
+    }
+    /// Returns whether the state's walk became the best one
+    fn improve(&self, state: &State) -> bool {
+        let mut min_walk = self.min_walk.lock().unwrap();
+        let better = match &(*min_walk) {
+            Some(ms) if ms.len() == state.len && self.deterministic => state.to_walk() < *ms,
+            Some(ms) => ms.len() > state.len,
+            None => true,
+        };
+        if better {
+            *min_walk = Some(state.to_walk());
+        }
+        better
+    }
+    fn pop(&self, id: usize) -> Option<Bounded> {
+        if let Some(state) = self.open_sets[id].lock().unwrap().pop() {
+            return Some(state);
+        }
+        (1..self.open_sets.len())
+            .map(|offset| (id + offset) % self.open_sets.len())
+            .find_map(|victim| self.open_sets[victim].lock().unwrap().pop())
+    }
+    /// Returns how many states are waiting across all workers
+    fn push(&self, id: usize, states: Vec<State>) -> usize {
+        let states: Vec<Bounded> = states
+            .into_iter()
+            .filter_map(|state| {
+                self.lower_bound(&state)
+                    .map(|bound| Bounded { bound, state })
+            })
+            .collect();
+        let count = states.len();
+        let pending = self.pending.fetch_add(count, AtomicOrdering::SeqCst) + count;
+        self.open_sets[id].lock().unwrap().extend(states);
+        if count > 0 && self.parked.load(AtomicOrdering::SeqCst) > 0 {
+            let _idle = self.idle.lock().unwrap();
+            self.wake.notify_all();
+        }
+        pending
+    }
+    fn done(&self) {
+        if self.pending.fetch_sub(1, AtomicOrdering::SeqCst) == 1 {
+            let _idle = self.idle.lock().unwrap();
+            self.wake.notify_all();
+        }
+    }
+    /// Sleeps until states are pushed or the last one is done, a parked worker registers
+    /// before looking at the open sets so a push in between can't be missed
+    fn park(&self) {
+        let idle = self.idle.lock().unwrap();
+        self.parked.fetch_add(1, AtomicOrdering::SeqCst);
+        let empty = self.open_sets.iter().all(|s| s.lock().unwrap().is_empty());
+        if empty && !self.finished() {
+            let _ = self.wake.wait_timeout(idle, PARK_TIMEOUT).unwrap();
+        }
+        self.parked.fetch_sub(1, AtomicOrdering::SeqCst);
+    }
+    fn finished(&self) -> bool {
+        self.pending.load(AtomicOrdering::SeqCst) == 0
+    }
+    fn bound(&self) -> usize {
+        match &*self.min_walk.lock().unwrap() {
+            Some(ms) => ms.len(),
+            None => usize::MAX,
+        }
+    }
+}
+
+/// Returns true when stopped by the limits rather than by running out of work. Frames go
+/// through the channel so recording doesn't hold up the other workers
+fn worker(
+    id: usize,
     end: usize,
-    queue: Arc<RwLock<VecDeque<State>>>,
-    min_walk: Arc<Mutex<Option<Vec<usize>>>>,
-    sync_flex: Arc<Mutex<isize>>,
-) {
+    scheduler: Arc<Scheduler>,
+    limits: Limits,
+    frames: Option<Sender<Frame>>,
+) -> (bool, SearchStats) {
+    let mut stats = SearchStats::default();
     loop {
-        let state = match queue.write() {
-            Ok(mut rw) => match rw.pop_front() {
-                Some(state) => {
-                    *sync_flex.lock().unwrap() = 0;
-
-                    state
-                }
-                None => {
-                    *sync_flex.lock().unwrap() += 1;
-                    let a = *sync_flex.lock().unwrap();
-                    if a >= 15 {
-                        return;
-                    }
-                    continue;
+        if limits.exceeded() {
+            return (true, stats);
+        }
+        let state = match scheduler.pop(id) {
+            // the best walk may have improved since this one was pushed
+            Some(Bounded { bound, state }) if !scheduler.promising(bound) => {
+                stats.pruned += 1;
+                if let Some(frames) = &frames {
+                    let _ = frames.send(Frame {
+                        dropped: vec![state.position()],
+                        ..Default::default()
+                    });
                 }
-            },
-            Err(_) => continue,
+                scheduler.done();
+                continue;
+            }
+            Some(Bounded { state, .. }) => state,
+            None if scheduler.finished() => return (false, stats),
+            None => {
+                scheduler.park();
+                continue;
+            }
         };
         let next_states = state.next_states();
+        state.tally(&next_states, &mut stats);
+        let (at_end, mut states_to_push): (Vec<State>, Vec<State>) =
+            next_states.into_iter().partition(|state| state.at_end(end));
+        let mut best = None;
+        for s in at_end.iter() {
+            if scheduler.improve(s) {
+                best = Some(s);
+            }
+        }
+        let generated = states_to_push.len();
+        states_to_push.retain(|st| {
+            scheduler
+                .lower_bound(st)
+                .map_or(false, |b| scheduler.promising(b))
+                && scheduler.first_visit(st)
+        });
+        stats.pruned += generated - states_to_push.len();
 
-        let min_state = next_states
-            .clone()
-            .into_iter()
-            .filter(|s| s.at_end(end))
-            .reduce(|min, c| if c < min { c } else { min });
-        let mut states_to_push: VecDeque<State> = next_states
+        if let Some(frames) = &frames {
+            let _ = frames.send(Frame {
+                current: state.to_walk(),
+                pushed: states_to_push.iter().map(State::position).collect(),
+                best: best.map(State::to_walk),
+                ..Default::default()
+            });
+        }
+        let open = scheduler.push(id, states_to_push);
+        stats.peak_open = stats.peak_open.max(open);
+        scheduler.done();
+    }
+}
+
+fn transpose(graph: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    (0..graph.len())
+        .map(|to| graph.iter().map(|from| from[to]).collect())
+        .collect()
+}
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[allow(unused, clippy::too_many_arguments)]
+pub fn parallel_backtrack(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    config: &ParallelConfig,
+    limits: &Limits,
+    stats: &mut SearchStats,
+    recorder: Option<&mut dyn Recorder>,
+) -> Outcome<Option<Vec<usize>>> {
+    let remaining = bfs_distances(end, &transpose(graph), true);
+    // wasm32 can't spawn threads
+    let config = ParallelConfig {
+        threads: match cfg!(target_arch = "wasm32") {
+            true => 1,
+            false => config.threads,
+        },
+        ..config.clone()
+    };
+    let scheduler = Arc::new(Scheduler::new(
+        &config,
+        State::new(start, graph, keys),
+        remaining,
+    ));
+    let (sender, frames) = mpsc::channel();
+    let sender = recorder.is_some().then_some(sender);
+    // a single worker runs on the caller's thread
+    let results = match scheduler.open_sets.len() {
+        1 => vec![worker(0, end, scheduler.clone(), limits.clone(), sender)],
+        workers => (0..workers)
+            .map(|id| {
+                let scheduler = scheduler.clone();
+                let limits = limits.clone();
+                let sender = sender.clone();
+                thread::spawn(move || worker(id, end, scheduler, limits, sender))
+            })
+            .collect::<Vec<_>>()
             .into_iter()
-            .filter(|state| !state.at_end(end))
-            .collect();
-        match min_state {
-            Some(s) => match min_walk.lock() {
-                Ok(mut min_s) => {
-                    let len = match &(*min_s) {
-                        Some(ms) => ms.len(),
-                        None => usize::MAX,
-                    };
-                    if len > s.walk.len() {
-                        std::mem::replace::<Option<Vec<usize>>>(&mut *min_s, Some(s.walk));
-                        println!("{:?}", min_s.clone().unwrap());
+            .map(|thread| thread.join().unwrap())
+            .collect(),
+    };
+    let mut interrupted = false;
+    let per_thread = results.iter().map(|(_, s)| s.expanded).collect();
+    for (stopped, worker_stats) in results {
+        interrupted |= stopped;
+        stats.merge(&worker_stats);
+    }
+    stats.merge(&SearchStats {
+        per_thread,
+        ..Default::default()
+    });
+    if let Some(recorder) = recorder {
+        for frame in frames.try_iter() {
+            recorder.record(frame);
+        }
+    }
+    let min_walk = scheduler.min_walk.lock().unwrap().take();
+    match interrupted {
+        true => Outcome::Partial(min_walk),
+        false => Outcome::Complete(min_walk),
+    }
+}
+
+struct WalkDag {
+    positions: Vec<usize>,
+    parents: Vec<Vec<usize>>,
+    depth: Vec<usize>,
+    counts: Vec<usize>,
+    ends: Vec<usize>,
+}
+
+/// Layered BFS over states, every node keeps all of its predecessors from the previous layer
+fn shortest_walk_dag(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    limits: &Limits,
+    stats: &mut SearchStats,
+    mut recorder: Option<&mut dyn Recorder>,
+) -> Outcome<WalkDag> {
+    let initial_state = State::new(start, graph, keys);
+    let mut dag = WalkDag {
+        positions: vec![start],
+        parents: vec![vec![]],
+        depth: vec![0],
+        counts: vec![1],
+        ends: vec![],
+    };
+    if initial_state.at_end(end) {
+        dag.ends.push(0);
+        return Outcome::Complete(dag);
+    }
+    let mut index: HashMap<StateKey, usize> = HashMap::new();
+    index.insert(initial_state.key(), 0);
+    let mut frontier = vec![(0usize, initial_state)];
+    while !frontier.is_empty() && dag.ends.is_empty() {
+        stats.peak_open = stats.peak_open.max(frontier.len());
+        let mut next_frontier = vec![];
+        for (node, state) in frontier.iter() {
+            let node = *node;
+            if limits.exceeded() {
+                dag.ends.clear();
+                return Outcome::Partial(dag);
+            }
+            let next_states = state.next_states();
+            state.tally(&next_states, stats);
+            let pushed_from = next_frontier.len();
+            for next in next_states {
+                let key = next.key();
+                match index.get(&key) {
+                    Some(&i) if dag.depth[i] == dag.depth[node] + 1 => {
+                        dag.parents[i].push(node);
+                        dag.counts[i] = dag.counts[i].saturating_add(dag.counts[node]);
+                        stats.pruned += 1;
+                    }
+                    Some(_) => stats.pruned += 1,
+                    None => {
+                        let i = dag.positions.len();
+                        index.insert(key, i);
+                        dag.depth.push(dag.depth[node] + 1);
+                        dag.positions.push(next.position());
+                        dag.parents.push(vec![node]);
+                        dag.counts.push(dag.counts[node]);
+                        if next.at_end(end) {
+                            dag.ends.push(i);
+                        } else {
+                            next_frontier.push((i, next));
+                        }
                     }
                 }
-                Err(_) => unreachable!(),
-            },
-            None => {}
-        }
-        let len;
-        match min_walk.lock() {
-            Ok(min_s) => {
-                len = match &(*min_s) {
-                    Some(ms) => ms.len(),
-                    None => usize::MAX,
-                };
             }
-            Err(_) => unreachable!(),
+            record(&mut recorder, || Frame {
+                current: state.to_walk(),
+                pushed: next_frontier[pushed_from..]
+                    .iter()
+                    .map(|(_, s)| s.position())
+                    .collect(),
+                ..Default::default()
+            });
         }
-        states_to_push = states_to_push
-            .into_iter()
-            .filter(|st| st.walk.len() < len)
-            .collect();
+        frontier = next_frontier;
+    }
+    Outcome::Complete(dag)
+}
 
-        match queue.write() {
-            Ok(mut queue) => queue.append(&mut states_to_push),
-            Err(_) => {}
+/// Length of the optimal walk and the number of distinct walks of that length
+#[allow(unused)]
+pub fn count_optimal_walks(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    limits: &Limits,
+) -> Outcome<Option<(usize, usize)>> {
+    let mut stats = SearchStats::default();
+    shortest_walk_dag(start, end, graph, keys, limits, &mut stats, None).map(|dag| {
+        let length = dag.ends.first().map(|e| dag.depth[*e] + 1)?;
+        let count = dag
+            .ends
+            .iter()
+            .fold(0usize, |acc, e| acc.saturating_add(dag.counts[*e]));
+        Some((length, count))
+    })
+}
+
+/// Lazily yields every optimal walk, each one exactly once
+pub struct OptimalWalks {
+    dag: WalkDag,
+    stack: Vec<(usize, usize)>, // (node, next parent to visit)
+}
+
+impl Iterator for OptimalWalks {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, cursor) = match self.stack.last_mut() {
+                Some(top) => top,
+                None => {
+                    let end = self.dag.ends.pop()?;
+                    self.stack.push((end, 0));
+                    continue;
+                }
+            };
+            let parents = &self.dag.parents[*node];
+            if parents.is_empty() {
+                let walk = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .map(|(n, _)| self.dag.positions[*n])
+                    .collect();
+                self.stack.pop();
+                return Some(walk);
+            }
+            match parents.get(*cursor) {
+                Some(&parent) => {
+                    *cursor += 1;
+                    self.stack.push((parent, 0));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
         }
     }
 }
 
-const NUM_THREADS: isize = 16;
+/// A partial outcome yields no walks, the optimal length isn't known yet
 #[allow(unused)]
-pub fn parallel_backtrack(start: usize, end: usize, graph: &[Vec<u8>], keys: &[bool]) {
-    let inital_state = State {
-        walk: vec![start],
-        doors_opened: HashSet::new(),
-        keys_pickedup: vec![false; graph.len()],
-        graph: Arc::new(graph.to_owned()),
-        keys: Arc::new(keys.to_owned()),
-    };
-    let queue = Arc::new(RwLock::new(VecDeque::<State>::new()));
-    let min_walk: Arc<Mutex<Option<Vec<usize>>>> = Arc::new(Mutex::new(None));
-    let sync_flex: Arc<Mutex<isize>> = Arc::new(Mutex::new(0));
-    queue.write().unwrap().push_front(inital_state);
-    let mut handles = vec![];
-    for i in 0..NUM_THREADS {
-        let queue = queue.clone();
-        let min_walk = min_walk.clone();
-        let sync_flex = sync_flex.clone();
-        handles.push(thread::spawn(move || {
-            worker(end, queue, min_walk, sync_flex)
-        }));
+pub fn optimal_walks(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    limits: &Limits,
+    stats: &mut SearchStats,
+    recorder: Option<&mut dyn Recorder>,
+) -> Outcome<OptimalWalks> {
+    shortest_walk_dag(start, end, graph, keys, limits, stats, recorder)
+        .map(|dag| OptimalWalks { dag, stack: vec![] })
+}
+
+/// Returns the walks counted and whether the limits stopped the count early
+fn count_walks_from(
+    state: &State,
+    end: usize,
+    max_len: usize,
+    on_walk: &mut HashSet<StateKey>,
+    limits: &Limits,
+) -> (usize, bool) {
+    if state.at_end(end) {
+        return (1, false);
+    }
+    if state.len >= max_len {
+        return (0, false);
     }
-    for thread in handles.into_iter() {
-        thread.join().unwrap();
+    let mut count = 0usize;
+    for next in state.next_states() {
+        if limits.exceeded() {
+            return (count, true);
+        }
+        let key = next.key();
+        if on_walk.contains(&key) {
+            continue;
+        }
+        on_walk.insert(key.clone());
+        let (walks, stopped) = count_walks_from(&next, end, max_len, on_walk, limits);
+        on_walk.remove(&key);
+        count = count.saturating_add(walks);
+        if stopped {
+            return (count, true);
+        }
+    }
+    (count, false)
+}
+
+/// Number of walks to the end with at most `max_len` fields that never repeat a state,
+/// i.e. never come back to a field holding the same keys and opened doors.
+/// A partial outcome holds the walks counted so far
+#[allow(unused)]
+pub fn count_walks(
+    start: usize,
+    end: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    max_len: usize,
+    limits: &Limits,
+) -> Outcome<usize> {
+    let initial_state = State::new(start, graph, keys);
+    let mut on_walk = HashSet::from([initial_state.key()]);
+    match count_walks_from(&initial_state, end, max_len, &mut on_walk, limits) {
+        (count, true) => Outcome::Partial(count),
+        (count, false) => Outcome::Complete(count),
     }
 }
 
@@ -313,6 +1127,7 @@ pub fn key_pickup(
     graph: &mut [Vec<u8>],
     keys: &mut [bool],
     inventory: isize,
+    stats: &mut SearchStats,
 ) -> Option<(Vec<usize>, isize)> {
     let mut pikcup_path = vec![];
     let mut key_inventory = inventory;
@@ -327,6 +1142,7 @@ pub fn key_pickup(
         }
         let mut current = path[f];
         'inner: for (key, _, doors) in bfs_closest_keys(current, graph, keys, true) {
+            stats.keys_considered += 1;
             if doors > key_inventory as usize {
                 continue 'inner;
             }
@@ -485,3 +1301,251 @@ pub fn bfs_shortest_path(
         ignore_doors,
     )
 }
+
+#[allow(unused)]
+pub fn bfs_distances(start: usize, graph: &[Vec<u8>], ignore_doors: bool) -> Vec<Option<usize>> {
+    let matching = |_: usize, _: usize, _: &[Vec<u8>], _: &[bool]| true;
+    let action = |current: usize, _: &[Vec<u8>], came_from: &[Option<usize>], _: bool| {
+        BfsActionResult::Accumulate((current, traverse_path(came_from, current).len() - 1))
+    };
+    let mut distances = vec![None; graph.len()];
+    for (field, distance) in bfs(
+        start,
+        0,
+        graph,
+        &[],
+        Box::new(matching),
+        Box::new(action),
+        ignore_doors,
+    )
+    .unwrap_or_default()
+    {
+        distances[field] = Some(distance);
+    }
+    distances
+}
+
+/// For every field the length of the shortest walk from `start` that gets there under the
+/// key rules, and the fewest keys a walk that short spends on doors. Fields no legal walk
+/// reaches are `None`. Breadth first over fields and inventories like [`optimal_walks`], so
+/// it grows with the ways to pick up keys and open doors, a partial outcome has the fields
+/// reached before the limits were hit
+#[allow(unused)]
+pub fn reachability(
+    start: usize,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    limits: &Limits,
+) -> Outcome<Vec<Option<(usize, usize)>>> {
+    let initial_state = State::new(start, graph, keys);
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; graph.len()];
+    best[start] = Some((0, 0));
+    let mut seen = HashSet::from([initial_state.key()]);
+    let mut frontier = vec![initial_state];
+    let mut distance = 0;
+    while !frontier.is_empty() {
+        distance += 1;
+        let mut next_frontier = vec![];
+        for state in frontier {
+            if limits.exceeded() {
+                return Outcome::Partial(best);
+            }
+            for next in state.next_states() {
+                if !seen.insert(next.key()) {
+                    continue;
+                }
+                let spent = next.doors_opened.count();
+                match &mut best[next.position()] {
+                    Some((keys, d)) if *d == distance => *keys = (*keys).min(spent),
+                    Some(_) => {}
+                    slot => *slot = Some((spent, distance)),
+                }
+                next_frontier.push(next);
+            }
+        }
+        frontier = next_frontier;
+    }
+    Outcome::Complete(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(len: usize, edges: &[(usize, usize, u8)]) -> Vec<Vec<u8>> {
+        let mut graph = vec![vec![0u8; len]; len];
+        for (a, b, edge) in edges {
+            graph[*a][*b] = *edge;
+            graph[*b][*a] = *edge;
+        }
+        graph
+    }
+
+    #[test]
+    fn reachability_follows_the_key_rules() {
+        // 0 - 1 = 2 - 3 with a door between 1 and 2, the key on 3 is behind it
+        let mut edges = vec![(0, 1, 1), (1, 2, 255), (2, 3, 1)];
+        let mut keys = vec![false; 5];
+        keys[3] = true;
+        let limits = Limits::default();
+        let reach = |edges: &[(usize, usize, u8)], keys: &[bool]| {
+            reachability(0, &graph(5, edges), keys, &limits).into_inner()
+        };
+        assert_eq!(
+            reach(&edges, &keys),
+            vec![Some((0, 0)), Some((0, 1)), None, None, None]
+        );
+        // a key on 4 next to 0 opens it, the walk to 2 goes 0 4 0 1 2
+        edges.push((0, 4, 1));
+        keys[4] = true;
+        assert_eq!(
+            reach(&edges, &keys),
+            vec![
+                Some((0, 0)),
+                Some((0, 1)),
+                Some((1, 4)),
+                Some((1, 5)),
+                Some((0, 1))
+            ]
+        );
+    }
+
+    #[test]
+    fn count_walks_is_partial_only_when_stopped() {
+        let graph = graph(3, &[(0, 1, 1), (1, 2, 1), (0, 2, 1)]);
+        let keys = vec![false; 3];
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let cancelled = Limits {
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        // nothing left to search when the start is the end
+        assert_eq!(
+            count_walks(2, 2, &graph, &keys, 5, &cancelled),
+            Outcome::Complete(1)
+        );
+        assert_eq!(
+            count_walks(0, 2, &graph, &keys, 5, &cancelled),
+            Outcome::Partial(0)
+        );
+        assert_eq!(
+            count_walks(0, 2, &graph, &keys, 5, &Limits::default()),
+            Outcome::Complete(2)
+        );
+    }
+
+    #[test]
+    fn greedy_keeps_only_finished_detours_when_stopped() {
+        // 0 = 1 is a door, a* would walk through it for free
+        let graph = graph(3, &[(0, 1, 255), (1, 2, 1)]);
+        let keys = vec![false; 3];
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let cancelled = Limits {
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            greedy_pathfind(0, 2, &graph, &keys, &cancelled, &mut stats, None),
+            Outcome::Partial(None)
+        );
+    }
+
+    #[test]
+    fn optimal_walks_are_counted_and_listed_once() {
+        // 0 - 1, 0 - 2, 1 - 3 and 2 - 3 around a square, with a long way round through 4
+        let mut edges = vec![
+            (0, 1, 1),
+            (0, 2, 1),
+            (1, 3, 1),
+            (2, 3, 1),
+            (0, 4, 1),
+            (4, 3, 1),
+        ];
+        let mut keys = vec![false; 5];
+        let limits = Limits::default();
+        let walks = |edges: &[(usize, usize, u8)], keys: &[bool]| {
+            let graph = graph(5, edges);
+            let mut walks: Vec<Vec<usize>> = optimal_walks(
+                0,
+                3,
+                &graph,
+                keys,
+                &limits,
+                &mut SearchStats::default(),
+                None,
+            )
+            .into_inner()
+            .collect();
+            walks.sort();
+            let count = count_optimal_walks(0, 3, &graph, keys, &limits).into_inner();
+            (count, walks)
+        };
+        assert_eq!(
+            walks(&edges, &keys),
+            (
+                Some((3, 3)),
+                vec![vec![0, 1, 3], vec![0, 2, 3], vec![0, 4, 3]]
+            )
+        );
+        // locked doors without keys leave one walk, a key on 1 opens the door behind it
+        edges[3].2 = 255;
+        edges[5].2 = 255;
+        assert_eq!(walks(&edges, &keys), (Some((3, 1)), vec![vec![0, 1, 3]]));
+        keys[2] = true;
+        assert_eq!(
+            walks(&edges, &keys),
+            (Some((3, 2)), vec![vec![0, 1, 3], vec![0, 2, 3]])
+        );
+        assert_eq!(walks(&edges[3..], &keys), (None, vec![]));
+    }
+
+    #[test]
+    fn deduplicate_path_merges_repeats() {
+        assert_eq!(deduplicate_path(&[0, 1, 1, 2, 1, 1]), vec![0, 1, 2, 1]);
+        assert_eq!(deduplicate_path(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn key_cumsum_counts_doors_ahead() {
+        let mut consumed = vec![false; 4];
+        consumed[2] = true;
+        let mut keys = vec![false; 4];
+        assert_eq!(
+            key_cumsum(&[0, 1, 2, 3], &consumed, &keys),
+            vec![1, 1, 0, 0]
+        );
+        keys[1] = true;
+        assert_eq!(
+            key_cumsum(&[0, 1, 2, 3], &consumed, &keys),
+            vec![0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn key_pickup_detours_to_closest_key() {
+        let mut maze = graph(4, &[(0, 1, 1), (1, 2, 255), (0, 3, 1)]);
+        let mut keys = vec![false, false, false, true];
+        let mut consumed = vec![false; 4];
+        consumed[2] = true;
+        let cumsum = key_cumsum(&[0, 1, 2], &consumed, &keys);
+        let mut stats = SearchStats::default();
+        let pickup = key_pickup(&[0, 1, 2], &cumsum, &mut maze, &mut keys, 0, &mut stats);
+        assert_eq!(pickup, Some((vec![0, 3], 1)));
+        assert!(!keys[3]);
+    }
+
+    #[test]
+    fn key_pickup_fails_without_keys() {
+        let mut maze = graph(3, &[(0, 1, 1), (1, 2, 255)]);
+        let mut keys = vec![false; 3];
+        let mut consumed = vec![false; 3];
+        consumed[2] = true;
+        let cumsum = key_cumsum(&[0, 1, 2], &consumed, &keys);
+        let mut stats = SearchStats::default();
+        let pickup = key_pickup(&[0, 1, 2], &cumsum, &mut maze, &mut keys, 0, &mut stats);
+        assert_eq!(pickup, None);
+    }
+}
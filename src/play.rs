@@ -0,0 +1,178 @@
+use crate::{
+    field_center, field_lines,
+    replay::{Violation, Walker},
+    solver::{BfsSolver, Problem, Rules, Solver},
+    terminal::{self, RawMode},
+    Field, Labyrinth, COLUMN_SIZE,
+};
+use std::io::{self, Write};
+
+const PLAYER: &str = "🙂";
+
+/// The field as the player sees it: keys picked up are gone and opened doors are passages
+fn shown_field(lab: &Labyrinth, walker: &Walker, index: usize) -> Field {
+    let (row, col) = (index / COLUMN_SIZE, index % COLUMN_SIZE);
+    let mut field = lab.0[row][col];
+    field.contains_key &= !walker.picked_up(index);
+    let open = |door: &mut bool, path: &mut bool, neighbour: usize| {
+        if *door && walker.opened(index, neighbour) {
+            *door = false;
+            *path = true;
+        }
+    };
+    if row > 0 {
+        open(
+            &mut field.doors.north,
+            &mut field.paths.north,
+            index - COLUMN_SIZE,
+        );
+    }
+    if row + 1 < lab.0.len() {
+        open(
+            &mut field.doors.south,
+            &mut field.paths.south,
+            index + COLUMN_SIZE,
+        );
+    }
+    if col > 0 {
+        open(&mut field.doors.west, &mut field.paths.west, index - 1);
+    }
+    if col + 1 < COLUMN_SIZE {
+        open(&mut field.doors.east, &mut field.paths.east, index + 1);
+    }
+    field
+}
+
+fn draw_field(lab: &Labyrinth, walker: &Walker, index: usize) {
+    let field = shown_field(lab, walker, index);
+    let center = match walker.position() == index {
+        true => PLAYER,
+        false => field_center(&field),
+    };
+    let (row, col) = (index / COLUMN_SIZE, index % COLUMN_SIZE);
+    for (line, text) in field_lines(&field, center, &index.to_string())
+        .iter()
+        .enumerate()
+    {
+        terminal::move_to(row * 4 + line, col * 6);
+        print!("{text}");
+    }
+}
+
+fn neighbour(lab: &Labyrinth, field: usize, (dr, dc): (isize, isize)) -> Option<usize> {
+    let row = (field / COLUMN_SIZE).checked_add_signed(dr)?;
+    let col = (field % COLUMN_SIZE).checked_add_signed(dc)?;
+    (row < lab.0.len() && col < COLUMN_SIZE).then_some(row * COLUMN_SIZE + col)
+}
+
+fn refusal(violation: &Violation) -> &'static str {
+    match violation {
+        Violation::OneWay { .. } => "this passage only goes the other way",
+        Violation::LockedDoor { .. } => "the door is locked, find a key first",
+        _ => "a wall is in the way",
+    }
+}
+
+/// Lets a player walk from `start` to an end with the arrow keys or WASD, then compares the
+/// walk with the optimal one
+pub fn play(lab: &Labyrinth, start: usize, rules: &Rules) -> io::Result<()> {
+    let (graph, keys, ends) = lab.pathfind_matrix();
+    let mut walker = Walker::new(start, rules, &graph, &keys, &ends)
+        .map_err(|v| io::Error::new(io::ErrorKind::InvalidInput, v))?;
+    let status_row = lab.0.len() * 4;
+    let mut message = String::from("arrows or WASD to move, q to give up");
+    let raw = RawMode::enter()?;
+    terminal::clear_screen();
+    for index in 0..graph.len() {
+        draw_field(lab, &walker, index);
+    }
+    while !walker.trace().at_end {
+        let trace = walker.trace();
+        terminal::status_line(
+            status_row,
+            &format!(
+                "moves {}  keys in hand {}  {message}",
+                trace.moves.len(),
+                trace.keys_in_hand
+            ),
+        );
+        io::stdout().flush()?;
+        let key = match raw.read_key()? {
+            Some(key) => key,
+            None => continue,
+        };
+        if matches!(key, terminal::Key::Char('q') | terminal::Key::Escape) {
+            break;
+        }
+        let Some(to) = key
+            .direction()
+            .and_then(|d| neighbour(lab, walker.position(), d))
+        else {
+            message = String::from("that way is outside the maze");
+            continue;
+        };
+        let from = walker.position();
+        message = match walker.step(to) {
+            Ok(step) => match (step.opened_door, step.picked_up_key) {
+                (true, true) => "opened a door and picked up a key",
+                (true, false) => "opened a door",
+                (false, true) => "picked up a key",
+                (false, false) => "",
+            }
+            .to_string(),
+            Err(violation) => refusal(&violation).to_string(),
+        };
+        draw_field(lab, &walker, from);
+        draw_field(lab, &walker, to);
+    }
+    drop(raw);
+    terminal::move_to(status_row, 0);
+    print!("\x1b[J");
+
+    let moves = walker.trace().moves.len();
+    if !walker.trace().at_end {
+        println!("gave up after {moves} move(s)");
+        return Ok(());
+    }
+    println!("reached the end in {moves} move(s)");
+    let problem = Problem {
+        start,
+        rules: rules.clone(),
+        ..Default::default()
+    };
+    match BfsSolver.solve(lab, &problem).outcome.into_inner() {
+        Some(optimal) if optimal.cost == moves => println!("that's optimal!"),
+        Some(optimal) => println!(
+            "the shortest walk takes {} move(s): {:?}",
+            optimal.cost, optimal.walk
+        ),
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opened_doors_and_picked_keys_are_shown() {
+        // 0 - 1 = 2, the door declared by field 1 and its key on field 1
+        let mut fields = vec![Field::default(); COLUMN_SIZE];
+        fields[0].paths.east = true;
+        fields[1].paths.west = true;
+        fields[1].paths.east = true;
+        fields[1].doors.east = true;
+        fields[1].contains_key = true;
+        fields[2].paths.west = true;
+        let lab = Labyrinth(vec![fields]);
+        let (graph, keys, ends) = lab.pathfind_matrix();
+        let mut walker = Walker::new(0, &Rules::default(), &graph, &keys, &ends).unwrap();
+        assert!(shown_field(&lab, &walker, 1).doors.east);
+        walker.step(1).unwrap();
+        walker.step(2).unwrap();
+        let shown = shown_field(&lab, &walker, 1);
+        assert!(!shown.contains_key);
+        assert!(!shown.doors.east && shown.paths.east);
+    }
+}
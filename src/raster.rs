@@ -0,0 +1,329 @@
+use crate::{Edge, Labyrinth, COLUMN_SIZE};
+use std::io::{self, Write};
+
+type Rgb = [u8; 3];
+
+const WALL: Rgb = [34, 34, 34];
+const FLOOR: Rgb = [255, 255, 255];
+const EMPTY: Rgb = [200, 200, 200];
+const DOOR: Rgb = [160, 82, 45];
+const KEY: Rgb = [230, 184, 0];
+const END: Rgb = [210, 34, 34];
+const WALK: Rgb = [30, 111, 217];
+
+/// Pixels per field and per wall between fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scale {
+    pub cell: usize,
+    pub wall: usize,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Scale { cell: 4, wall: 1 }
+    }
+}
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>, // rows of RGB triples, top to bottom
+}
+
+impl Image {
+    fn pixel(&self, x: usize, y: usize) -> Rgb {
+        let i = (y * self.width + x) * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for row in y..y + height {
+            let start = (row * self.width + x) * 3;
+            for pixel in self.pixels[start..start + width * 3].chunks_exact_mut(3) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Binary PPM (P6)
+    pub fn write_ppm(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.pixels)
+    }
+
+    /// 8 bit RGB PNG, compressed by [`deflate`] so no compression library is needed
+    pub fn write_png(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]); // depth, RGB, deflate, no filter, no interlace
+
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks_exact(self.width * 3) {
+            raw.push(0); // filter type none
+            raw.extend_from_slice(row);
+        }
+        let mut zlib = vec![0x78, 0x01];
+        zlib.extend_from_slice(&deflate(&raw, self.width * 3 + 1));
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(w, b"IHDR", &header)?;
+        write_chunk(w, b"IDAT", &zlib)?;
+        write_chunk(w, b"IEND", &[])
+    }
+}
+
+/// Deflate bit stream, least significant bit first
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes go most significant bit first
+    fn push_code(&mut self, code: u32, count: u32) {
+        self.push(code.reverse_bits() >> (32 - count), count);
+    }
+
+    /// Literal, length and end of block symbols with the fixed Huffman codes
+    fn push_symbol(&mut self, symbol: u32) {
+        match symbol {
+            0..=143 => self.push_code(0x30 + symbol, 8),
+            144..=255 => self.push_code(0x190 + symbol - 144, 9),
+            256..=279 => self.push_code(symbol - 256, 7),
+            _ => self.push_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+const LENGTH_BASES: [u32; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [u32; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Index of the largest base not above `value` and what's left over for the extra bits
+fn base_code(bases: &[u32], value: u32) -> (u32, u32) {
+    let code = bases.partition_point(|base| *base <= value) - 1;
+    (code as u32, value - bases[code])
+}
+
+/// One fixed Huffman block. Rasters mostly repeat the pixel to the left or the row above, so
+/// the only matches tried are 3 bytes back and `row` bytes back, which keeps it linear
+fn deflate(data: &[u8], row: usize) -> Vec<u8> {
+    let mut bits = Bits::default();
+    bits.push(1, 1); // final block
+    bits.push(1, 2); // fixed codes
+    let distances = [3, row].into_iter().filter(|d| *d <= 32768);
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = distances
+            .clone()
+            .filter(|d| *d <= i)
+            .map(|d| {
+                let length = (i..data.len().min(i + 258))
+                    .take_while(|j| data[*j] == data[*j - d])
+                    .count();
+                (length, d)
+            })
+            .max()
+            .unwrap_or((0, 0));
+        if length < 3 {
+            bits.push_symbol(data[i] as u32);
+            i += 1;
+            continue;
+        }
+        let (code, extra) = base_code(&LENGTH_BASES, length as u32);
+        bits.push_symbol(257 + code);
+        bits.push(extra, LENGTH_EXTRA[code as usize]);
+        let (code, extra) = base_code(&DISTANCE_BASES, distance as u32);
+        bits.push_code(code, 5);
+        bits.push(extra, DISTANCE_EXTRA[code as usize]);
+        i += length;
+    }
+    bits.push_symbol(256);
+    bits.finish()
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    let crc = crc32(crc32(!0, kind), data);
+    w.write_all(&(!crc).to_be_bytes())
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // the sums can't overflow within 5552 bytes
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// One `scale.cell` block per field and `scale.wall` pixels between fields, the walk is
+/// painted over the fields and the passages it takes. Works on the fields directly rather than
+/// the adjacency matrix so mazes of millions of fields fit in memory
+pub fn render(lab: &Labyrinth, walk: Option<&[usize]>, scale: Scale) -> Image {
+    let Scale { cell, wall } = scale;
+    let step = cell + wall;
+    let rows = lab.0.len();
+    let (width, height) = (COLUMN_SIZE * step + wall, rows * step + wall);
+    let mut image = Image {
+        width,
+        height,
+        pixels: WALL.repeat(width * height),
+    };
+    let origin = |row: usize, col: usize| (wall + col * step, wall + row * step);
+    for (row, line) in lab.0.iter().enumerate() {
+        for (col, field) in line.iter().enumerate() {
+            let (x, y) = origin(row, col);
+            let color = if field.is_empty() {
+                EMPTY
+            } else if field.is_end {
+                END
+            } else if field.contains_key {
+                KEY
+            } else {
+                FLOOR
+            };
+            image.fill(x, y, cell, cell, color);
+            match lab.east_edge(row, col) {
+                Edge::Path => image.fill(x + cell, y, wall, cell, FLOOR),
+                Edge::Door => image.fill(x + cell, y, wall, cell, DOOR),
+                Edge::Wall => {}
+            }
+            match lab.south_edge(row, col) {
+                Edge::Path => image.fill(x, y + cell, cell, wall, FLOOR),
+                Edge::Door => image.fill(x, y + cell, cell, wall, DOOR),
+                Edge::Wall => {}
+            }
+        }
+    }
+    let fields = rows * COLUMN_SIZE;
+    for pair in walk.unwrap_or_default().windows(2) {
+        let (a, b) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+        if b >= fields {
+            continue;
+        }
+        let (x, y) = origin(a / COLUMN_SIZE, a % COLUMN_SIZE);
+        match b - a {
+            1 if b % COLUMN_SIZE != 0 => image.fill(x + cell, y, wall, cell, WALK),
+            COLUMN_SIZE => image.fill(x, y + cell, cell, wall, WALK),
+            _ => {}
+        }
+    }
+    for field in walk.unwrap_or_default() {
+        if *field < fields {
+            let (x, y) = origin(field / COLUMN_SIZE, field % COLUMN_SIZE);
+            // keys and ends on the walk keep a rim of their own color
+            let rim = match cell >= 3 && image.pixel(x, y) != FLOOR {
+                true => 1,
+                false => 0,
+            };
+            image.fill(x + rim, y + rim, cell - 2 * rim, cell - 2 * rim, WALK);
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_fields_passages_and_walk() {
+        let lab = crate::read_file("./labyrinth.txt").unwrap();
+        let scale = Scale { cell: 3, wall: 1 };
+        let image = render(&lab, Some(&[0, 9, 10]), scale);
+        assert_eq!((image.width, image.height), (37, 25));
+        assert_eq!(image.pixel(0, 0), WALL);
+        assert_eq!(image.pixel(2, 2), WALK); // field 0
+        assert_eq!(image.pixel(2, 4), WALK); // passage 0-9
+        assert_eq!(image.pixel(4, 2), DOOR); // door 0-1, not walked
+        let end = 47;
+        let (x, y) = (1 + end % COLUMN_SIZE * 4, 1 + end / COLUMN_SIZE * 4);
+        assert_eq!(image.pixel(x, y), END);
+    }
+
+    #[test]
+    fn png_checksums() {
+        assert_eq!(!crc32(!0, b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        let image = render(
+            &crate::read_file("./labyrinth.txt").unwrap(),
+            None,
+            Scale::default(),
+        );
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn deflate_repeats_pixels_and_rows() {
+        // checked against zlib: literals rgb, a 12 byte run 3 back, a 0, then 15 bytes 16 back
+        assert_eq!(
+            deflate(b"rgbrgbrgbrgbrgb\x00rgbrgbrgbrgbrgb", 16),
+            [43, 74, 79, 66, 70, 12, 104, 124, 0]
+        );
+        let image = render(
+            &crate::read_file("./labyrinth.txt").unwrap(),
+            None,
+            Scale::default(),
+        );
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        assert!(png.len() < image.pixels.len() / 5, "{}", png.len());
+    }
+}
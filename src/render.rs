@@ -0,0 +1,343 @@
+use crate::{
+    field_center, field_lines_painted,
+    theme::{paint, Colors, Part},
+    Edge, Field, Labyrinth, COLUMN_SIZE, HEAT,
+};
+use std::io::{self, Write};
+
+pub const NAMES: [&str; 3] = ["box", "ascii", "compact"];
+
+pub trait Renderer {
+    fn render(&self, lab: &Labyrinth, out: &mut dyn Write) -> io::Result<()>;
+
+    fn to_string(&self, lab: &Labyrinth) -> String {
+        let mut out = vec![];
+        self.render(lab, &mut out)
+            .expect("writing to a Vec can't fail");
+        String::from_utf8(out).expect("renderers write UTF-8")
+    }
+}
+
+/// Which fields the walk to highlight passes, none without colors
+fn walked(lab: &Labyrinth, colors: Option<&Colors>) -> Vec<bool> {
+    let mut walked = vec![false; lab.0.len() * COLUMN_SIZE];
+    for field in colors.map_or(&[][..], |c| c.walk) {
+        if let Some(w) = walked.get_mut(*field) {
+            *w = true;
+        }
+    }
+    walked
+}
+
+/// The key or end marker of a field in its color, `None` for other fields
+fn marker(field: &Field, colors: Option<&Colors>, end: &str, key: &str) -> Option<String> {
+    let theme = colors.map(|c| &c.theme);
+    if field.is_end {
+        Some(paint(theme, Part::End, end))
+    } else if field.contains_key {
+        Some(paint(theme, Part::Key, key))
+    } else {
+        None
+    }
+}
+
+/// Box-drawing characters, 6 columns and 4 lines per field, emoji for keys and the end.
+/// With `heat` every field is shaded by the keys spent to reach it and shows its distance,
+/// keys and ends keep their marker
+#[derive(Debug, Default, Clone)]
+pub struct BoxRenderer<'a> {
+    pub indices: bool,
+    pub heat: Option<&'a [Option<(usize, usize)>]>,
+    pub colors: Option<Colors<'a>>,
+}
+
+impl Renderer for BoxRenderer<'_> {
+    fn render(&self, lab: &Labyrinth, out: &mut dyn Write) -> io::Result<()> {
+        let colors = self.colors.as_ref();
+        let theme = colors.map(|c| &c.theme);
+        let walked = walked(lab, colors);
+        for (row, line) in lab.0.iter().enumerate() {
+            let boxes: Vec<[String; 4]> = line
+                .iter()
+                .enumerate()
+                .map(|(col, field)| {
+                    let index = row * COLUMN_SIZE + col;
+                    let (center, label) = match self.heat {
+                        Some(heat) => {
+                            let shade = match heat[index] {
+                                Some((keys, _)) => HEAT[keys.min(HEAT.len() - 1)],
+                                None => "╳╳",
+                            };
+                            let center = marker(field, colors, "🚩", "🗝️ ")
+                                .unwrap_or_else(|| shade.to_string());
+                            let label = heat[index].map_or(String::new(), |(_, d)| d.to_string());
+                            (center, label)
+                        }
+                        None => {
+                            let center = match marker(field, colors, "🚩", "🗝️ ") {
+                                Some(marker) => marker,
+                                None if walked[index] => paint(theme, Part::Walk, "██"),
+                                None => field_center(field).to_string(),
+                            };
+                            let label = match (self.indices, walked[index]) {
+                                (true, true) => paint(theme, Part::Walk, &format!("{index:>2}")),
+                                (true, false) => index.to_string(),
+                                (false, _) => String::new(),
+                            };
+                            (center, label)
+                        }
+                    };
+                    field_lines_painted(field, &center, &label, theme)
+                })
+                .collect();
+            for line in 0..4 {
+                for lines in &boxes {
+                    write!(out, "{}", lines[line])?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plain ASCII for logs and terminals without Unicode: `+`, `-` and `|` walls, `#` doors,
+/// `K` keys and `E` ends, each field sharing its walls with the neighbours
+#[derive(Debug, Default, Clone)]
+pub struct AsciiRenderer<'a> {
+    pub indices: bool,
+    pub colors: Option<Colors<'a>>,
+}
+
+impl Renderer for AsciiRenderer<'_> {
+    fn render(&self, lab: &Labyrinth, out: &mut dyn Write) -> io::Result<()> {
+        let colors = self.colors.as_ref();
+        let theme = colors.map(|c| &c.theme);
+        let walked = walked(lab, colors);
+        let last = (lab.0.len() * COLUMN_SIZE).saturating_sub(1);
+        let width = match self.indices {
+            true => (last.to_string().len() + 1).max(3),
+            false => 3,
+        };
+        let wall = |text: &str| paint(theme, Part::Wall, text);
+        let horizontal = |edge: Edge| match edge {
+            Edge::Wall => wall(&"-".repeat(width)),
+            Edge::Path => " ".repeat(width),
+            Edge::Door => format!(
+                "{}{}{}",
+                wall("-"),
+                paint(theme, Part::Door, &"#".repeat(width - 2)),
+                wall("-")
+            ),
+        };
+        let vertical = |edge: Edge| match edge {
+            Edge::Wall => wall("|"),
+            Edge::Path => " ".to_string(),
+            Edge::Door => paint(theme, Part::Door, "#"),
+        };
+        for (row, line) in lab.0.iter().enumerate() {
+            for col in 0..COLUMN_SIZE {
+                let north = match row {
+                    0 => Edge::Wall,
+                    _ => lab.south_edge(row - 1, col),
+                };
+                write!(out, "{}{}", wall("+"), horizontal(north))?;
+            }
+            writeln!(out, "{}", wall("+"))?;
+            for (col, field) in line.iter().enumerate() {
+                let west = match col {
+                    0 => Edge::Wall,
+                    _ => lab.east_edge(row, col - 1),
+                };
+                let index = row * COLUMN_SIZE + col;
+                let mark = marker(field, colors, "E", "K").unwrap_or_else(|| " ".to_string());
+                let inside = match self.indices && !field.is_empty() {
+                    true => format!("{:>1$}", index, width - 1),
+                    false => " ".repeat(width - 1),
+                };
+                let inside = match walked[index] {
+                    true => paint(theme, Part::Walk, &inside),
+                    false => inside,
+                };
+                match self.indices {
+                    true => write!(out, "{}{mark}{inside}", vertical(west))?,
+                    false => write!(out, "{} {mark} ", vertical(west))?,
+                }
+            }
+            writeln!(out, "{}", wall("|"))?;
+        }
+        writeln!(
+            out,
+            "{}",
+            wall(&format!(
+                "{}+",
+                format!("+{}", "-".repeat(width)).repeat(COLUMN_SIZE)
+            ))
+        )
+    }
+}
+
+const LIGHT: [char; 16] = [
+    '·', '╵', '╶', '└', '╷', '│', '┌', '├', '╴', '┘', '─', '┴', '┐', '┤', '┬', '┼',
+];
+const HEAVY: [char; 16] = [
+    '·', '╹', '╺', '┗', '╻', '┃', '┏', '┣', '╸', '┛', '━', '┻', '┓', '┫', '┳', '╋',
+];
+
+/// One character per field showing its open sides, heavy lines for fields next to a door,
+/// `k` for keys, `E` for ends and a space for fields that aren't part of the maze
+#[derive(Debug, Default, Clone)]
+pub struct CompactRenderer<'a> {
+    pub colors: Option<Colors<'a>>,
+}
+
+impl Renderer for CompactRenderer<'_> {
+    fn render(&self, lab: &Labyrinth, out: &mut dyn Write) -> io::Result<()> {
+        let colors = self.colors.as_ref();
+        let theme = colors.map(|c| &c.theme);
+        let walked = walked(lab, colors);
+        let rows = lab.0.len();
+        for (row, line) in lab.0.iter().enumerate() {
+            let text: String = line
+                .iter()
+                .enumerate()
+                .map(|(col, field)| {
+                    if field.is_empty() {
+                        return " ".to_string();
+                    } else if let Some(marker) = marker(field, colors, "E", "k") {
+                        return marker;
+                    }
+                    let edges = [
+                        (row > 0).then(|| lab.south_edge(row - 1, col)),
+                        Some(lab.east_edge(row, col)),
+                        (row + 1 < rows).then(|| lab.south_edge(row, col)),
+                        (col > 0).then(|| lab.east_edge(row, col - 1)),
+                    ];
+                    let mut sides = 0;
+                    let mut door = false;
+                    for (bit, edge) in edges.iter().enumerate() {
+                        if let Some(Edge::Path | Edge::Door) = edge {
+                            sides |= 1 << bit;
+                        }
+                        door |= *edge == Some(Edge::Door);
+                    }
+                    let glyph = match door {
+                        true => HEAVY[sides],
+                        false => LIGHT[sides],
+                    };
+                    let part = match (walked[row * COLUMN_SIZE + col], door) {
+                        (true, _) => Part::Walk,
+                        (false, true) => Part::Door,
+                        (false, false) => Part::Wall,
+                    };
+                    paint(theme, part, &glyph.to_string())
+                })
+                .collect();
+            writeln!(out, "{text}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn by_name<'a>(
+    name: &str,
+    indices: bool,
+    colors: Option<Colors<'a>>,
+) -> Option<Box<dyn Renderer + 'a>> {
+    match name {
+        "box" => Some(Box::new(BoxRenderer {
+            indices,
+            heat: None,
+            colors,
+        })),
+        "ascii" => Some(Box::new(AsciiRenderer { indices, colors })),
+        "compact" => Some(Box::new(CompactRenderer { colors })),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Labyrinth {
+        crate::read_file("./labyrinth.txt").unwrap()
+    }
+
+    #[test]
+    fn box_renderer_hides_indices() {
+        let lab = sample();
+        let with = BoxRenderer {
+            indices: true,
+            ..Default::default()
+        }
+        .to_string(&lab);
+        let without = BoxRenderer::default().to_string(&lab);
+        assert_eq!(with.lines().count(), lab.0.len() * 4);
+        assert!(with.contains(" 47 ") && !without.contains(" 47 "));
+        assert!(without.contains("🚩"));
+    }
+
+    #[test]
+    fn heat_map_keeps_the_markers() {
+        let lab = sample();
+        let heat = lab.reachability(0, &Default::default()).into_inner();
+        let text = BoxRenderer {
+            heat: Some(&heat),
+            ..Default::default()
+        }
+        .to_string(&lab);
+        assert!(text.contains("🚩") && text.contains("🗝️"));
+        assert!(text.contains(HEAT[0]));
+    }
+
+    #[test]
+    fn ascii_renderer_shares_walls() {
+        let lab = sample();
+        let text = AsciiRenderer {
+            indices: true,
+            colors: None,
+        }
+        .to_string(&lab);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), lab.0.len() * 2 + 1);
+        assert!(text.is_ascii());
+        assert!(lines.iter().all(|l| l.len() == COLUMN_SIZE * 4 + 1));
+        assert!(lines[1].starts_with("|  0#  1 "), "{}", lines[1]);
+        assert!(text.contains(" E47 "));
+        assert!(text.contains('K'));
+    }
+
+    #[test]
+    fn compact_renderer_is_one_char_per_field() {
+        let lab = sample();
+        let text = CompactRenderer::default().to_string(&lab);
+        assert_eq!(text.lines().count(), lab.0.len());
+        assert!(text.lines().all(|l| l.chars().count() == COLUMN_SIZE));
+        assert_eq!(text.lines().next().unwrap().chars().next(), Some('┏'));
+    }
+
+    #[test]
+    fn colors_paint_the_walk() {
+        let lab = sample();
+        let colors = Colors {
+            theme: Default::default(),
+            walk: &[0, 9],
+        };
+        let walk = colors.theme.paint(Part::Walk, "┏");
+        for name in NAMES {
+            let plain = by_name(name, true, None).unwrap().to_string(&lab);
+            assert!(!plain.contains('\x1b'), "{name}");
+            let painted = by_name(name, true, Some(colors.clone()))
+                .unwrap()
+                .to_string(&lab);
+            assert!(painted.contains("\x1b[91m"), "{name}"); // the end
+            assert!(painted.contains("\x1b[94m"), "{name}");
+        }
+        let compact = CompactRenderer {
+            colors: Some(colors),
+        }
+        .to_string(&lab);
+        assert!(compact.starts_with(&walk));
+    }
+}
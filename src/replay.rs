@@ -0,0 +1,332 @@
+use super::COLUMN_SIZE;
+use crate::solver::Rules;
+use std::{collections::HashSet, fmt};
+
+/// One move of a replayed walk and the inventory right after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub picked_up_key: bool,
+    pub opened_door: bool,
+    pub keys_in_hand: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub moves: Vec<Move>,
+    pub keys: Vec<usize>,           // fields the keys were picked up from
+    pub doors: Vec<(usize, usize)>, // doors opened, in the order of the walk
+    pub keys_in_hand: usize,
+    pub at_end: bool, // the walk stops on an end field
+}
+
+/// The first thing wrong with a walk, `step` is the index of the offending field in the walk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    Empty,
+    OutOfBounds { step: usize, field: usize },
+    NotAdjacent { step: usize, from: usize, to: usize },
+    Wall { step: usize, from: usize, to: usize },
+    OneWay { step: usize, from: usize, to: usize },
+    LockedDoor { step: usize, from: usize, to: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Empty => write!(f, "the walk is empty"),
+            Violation::OutOfBounds { step, field } => {
+                write!(f, "step {step}: field {field} is outside the maze")
+            }
+            Violation::NotAdjacent { step, from, to } => {
+                write!(f, "step {step}: {from} and {to} aren't neighbours")
+            }
+            Violation::Wall { step, from, to } => {
+                write!(f, "step {step}: wall between {from} and {to}")
+            }
+            Violation::OneWay { step, from, to } => {
+                write!(
+                    f,
+                    "step {step}: {from} -> {to} goes against a one-way passage"
+                )
+            }
+            Violation::LockedDoor { step, from, to } => {
+                write!(
+                    f,
+                    "step {step}: door between {from} and {to} and no key in hand"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl Trace {
+    pub fn to_json(&self) -> String {
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|m| {
+                format!(
+                    concat!(
+                        "{{\"from\":{},\"to\":{},\"picked_up_key\":{},",
+                        "\"opened_door\":{},\"keys_in_hand\":{}}}"
+                    ),
+                    m.from, m.to, m.picked_up_key, m.opened_door, m.keys_in_hand
+                )
+            })
+            .collect();
+        let doors: Vec<[usize; 2]> = self.doors.iter().map(|(a, b)| [*a, *b]).collect();
+        format!(
+            "{{\"moves\":[{}],\"keys\":{:?},\"doors\":{:?},\"keys_in_hand\":{},\"at_end\":{}}}",
+            moves.join(","),
+            self.keys,
+            doors,
+            self.keys_in_hand,
+            self.at_end
+        )
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, m) in self.moves.iter().enumerate() {
+            write!(f, "{:3}: {:3} -> {:3}", i + 1, m.from, m.to)?;
+            if m.opened_door {
+                write!(f, "  opened door")?;
+            }
+            if m.picked_up_key {
+                write!(f, "  picked up key")?;
+            }
+            writeln!(f, "  ({} in hand)", m.keys_in_hand)?;
+        }
+        writeln!(
+            f,
+            "{} move(s), keys {:?}, doors {:?}, {}",
+            self.moves.len(),
+            self.keys,
+            self.doors,
+            match self.at_end {
+                true => "reached the end",
+                false => "didn't reach an end",
+            }
+        )
+    }
+}
+
+fn adjacent(a: usize, b: usize) -> bool {
+    let (ra, ca) = (a / COLUMN_SIZE, a % COLUMN_SIZE);
+    let (rb, cb) = (b / COLUMN_SIZE, b % COLUMN_SIZE);
+    ra.abs_diff(rb) + ca.abs_diff(cb) == 1
+}
+
+/// Walks a maze one move at a time with the same rules the solvers follow: the key on the
+/// start field isn't picked up, keys are picked up when entering a field, a door takes one key
+/// in hand the first time it's passed and stays open both ways after that
+pub struct Walker<'a> {
+    graph: &'a [Vec<u8>],
+    keys: &'a [bool],
+    ends: &'a [bool],
+    rules: Rules,
+    position: usize,
+    picked_up: HashSet<usize>,
+    opened: HashSet<(usize, usize)>,
+    trace: Trace,
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(
+        start: usize,
+        rules: &Rules,
+        graph: &'a [Vec<u8>],
+        keys: &'a [bool],
+        ends: &'a [bool],
+    ) -> Result<Self, Violation> {
+        if start >= graph.len() {
+            return Err(Violation::OutOfBounds {
+                step: 0,
+                field: start,
+            });
+        }
+        Ok(Walker {
+            graph,
+            keys,
+            ends,
+            rules: rules.clone(),
+            position: start,
+            picked_up: HashSet::from([start]),
+            opened: HashSet::new(),
+            trace: Trace {
+                at_end: ends[start],
+                ..Default::default()
+            },
+        })
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    pub fn into_trace(self) -> Trace {
+        self.trace
+    }
+
+    pub fn picked_up(&self, field: usize) -> bool {
+        self.picked_up.contains(&field) && self.keys[field]
+    }
+
+    pub fn opened(&self, a: usize, b: usize) -> bool {
+        self.opened.contains(&(a.min(b), a.max(b)))
+    }
+
+    /// Moves to `to`, a rejected move leaves the walker where it was
+    pub fn step(&mut self, to: usize) -> Result<&Move, Violation> {
+        let (step, from) = (self.trace.moves.len() + 1, self.position);
+        if to >= self.graph.len() {
+            return Err(Violation::OutOfBounds { step, field: to });
+        }
+        if !adjacent(from, to) {
+            return Err(Violation::NotAdjacent { step, from, to });
+        }
+        let mut opened_door = false;
+        match self.graph[from][to] {
+            0 if self.graph[to][from] != 0 => return Err(Violation::OneWay { step, from, to }),
+            0 => return Err(Violation::Wall { step, from, to }),
+            255 if !self.rules.ignore_doors => {
+                let door = (from.min(to), from.max(to));
+                if !self.opened.contains(&door) {
+                    if self.trace.keys_in_hand == 0 {
+                        return Err(Violation::LockedDoor { step, from, to });
+                    }
+                    self.trace.keys_in_hand -= 1;
+                    self.opened.insert(door);
+                    self.trace.doors.push(door);
+                    opened_door = true;
+                }
+            }
+            _ => {}
+        }
+        let picked_up_key = self.keys[to] && self.picked_up.insert(to);
+        if picked_up_key {
+            self.trace.keys_in_hand += 1;
+            self.trace.keys.push(to);
+        }
+        self.position = to;
+        self.trace.at_end = self.ends[to];
+        self.trace.moves.push(Move {
+            from,
+            to,
+            picked_up_key,
+            opened_door,
+            keys_in_hand: self.trace.keys_in_hand,
+        });
+        Ok(self.trace.moves.last().unwrap())
+    }
+}
+
+/// Plays the whole walk, see [`Walker`] for the rules
+pub fn replay(
+    walk: &[usize],
+    rules: &Rules,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+    ends: &[bool],
+) -> Result<Trace, Violation> {
+    let first = *walk.first().ok_or(Violation::Empty)?;
+    let mut walker = Walker::new(first, rules, graph, keys, ends)?;
+    for field in &walk[1..] {
+        walker.step(*field)?;
+    }
+    Ok(walker.into_trace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 - 1 = 2 along the first row, = being a door, and a key on the field below 0
+    fn maze() -> (Vec<Vec<u8>>, Vec<bool>, Vec<bool>) {
+        let len = 2 * COLUMN_SIZE;
+        let mut graph = vec![vec![0u8; len]; len];
+        for (a, b, edge) in [(0, 1, 1), (1, 2, 255), (0, COLUMN_SIZE, 1)] {
+            graph[a][b] = edge;
+            graph[b][a] = edge;
+        }
+        let mut keys = vec![false; len];
+        keys[COLUMN_SIZE] = true;
+        let mut ends = vec![false; len];
+        ends[2] = true;
+        (graph, keys, ends)
+    }
+
+    #[test]
+    fn replays_a_legal_walk() {
+        let (graph, keys, ends) = maze();
+        let walk = [0, COLUMN_SIZE, 0, 1, 2, 1, 2];
+        let trace = replay(&walk, &Rules::default(), &graph, &keys, &ends).unwrap();
+        assert_eq!(trace.keys, vec![COLUMN_SIZE]);
+        assert_eq!(trace.doors, vec![(1, 2)]);
+        assert_eq!(trace.keys_in_hand, 0);
+        assert!(trace.at_end);
+        assert!(trace.moves[0].picked_up_key && trace.moves[3].opened_door);
+        assert!(!trace.moves[5].opened_door);
+    }
+
+    #[test]
+    fn reports_the_first_violation() {
+        let (mut graph, keys, ends) = maze();
+        let rules = Rules::default();
+        assert_eq!(
+            replay(&[], &rules, &graph, &keys, &ends),
+            Err(Violation::Empty)
+        );
+        assert_eq!(
+            replay(&[0, 1, 2], &rules, &graph, &keys, &ends),
+            Err(Violation::LockedDoor {
+                step: 2,
+                from: 1,
+                to: 2
+            })
+        );
+        assert_eq!(
+            replay(&[0, 2], &rules, &graph, &keys, &ends),
+            Err(Violation::NotAdjacent {
+                step: 1,
+                from: 0,
+                to: 2
+            })
+        );
+        assert_eq!(
+            replay(&[1, COLUMN_SIZE + 1], &rules, &graph, &keys, &ends),
+            Err(Violation::Wall {
+                step: 1,
+                from: 1,
+                to: COLUMN_SIZE + 1
+            })
+        );
+        graph[1][0] = 0;
+        assert_eq!(
+            replay(&[0, 1, 0], &rules, &graph, &keys, &ends),
+            Err(Violation::OneWay {
+                step: 2,
+                from: 1,
+                to: 0
+            })
+        );
+        assert!(replay(&[0, 99], &rules, &graph, &keys, &ends).is_err());
+    }
+
+    #[test]
+    fn doors_open_without_keys_when_ignored() {
+        let (graph, keys, ends) = maze();
+        let rules = Rules { ignore_doors: true };
+        let trace = replay(&[0, 1, 2], &rules, &graph, &keys, &ends).unwrap();
+        assert!(trace.doors.is_empty() && trace.at_end);
+    }
+}
@@ -0,0 +1,872 @@
+use crate::{
+    json::{self, Json},
+    pathfinder::{Budget, Limits, ParallelConfig},
+    raster, render,
+    solver::{self, Problem, Rules, Solver},
+    svg, Edge, Labyrinth, COLUMN_SIZE,
+};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Requests whose headers don't fit are refused
+const MAX_HEADERS: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub workers: usize,       // requests handled at the same time
+    pub max_body: usize,      // bytes
+    pub max_fields: usize,    // the adjacency matrix grows with the square of this
+    pub max_nodes: usize,     // nodes a solve may expand, bfs keeps about 1.5 KB for each
+    pub timeout: Duration,    // for the solvers, requests can ask for less
+    pub io_timeout: Duration, // for reading the whole request and writing the response
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            host: String::from("127.0.0.1"),
+            port: 8080,
+            workers: 8,
+            max_body: 1 << 20,
+            max_fields: 2500,
+            max_nodes: 50_000,
+            timeout: Duration::from_secs(5),
+            io_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            body: body.into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response::json(
+            status,
+            format!("{{\"error\":\"{}\"}}", json::escape(message)),
+        )
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(
+            w,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason(),
+            self.content_type,
+            self.body.len()
+        )?;
+        w.write_all(&self.body)?;
+        w.flush()
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn timed_out(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Reads from the stream until the deadline, a client sending a byte now and then can't
+/// stretch the request past it
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads one request that has to arrive by the deadline, the error is the response to send
+/// instead
+fn read_request(
+    stream: &mut TcpStream,
+    deadline: Instant,
+    config: &Config,
+) -> Result<Request, Response> {
+    let io_error = |e: io::Error| match timed_out(&e) {
+        true => Response::error(408, "request took too long to arrive"),
+        false => Response::error(400, &e.to_string()),
+    };
+    let mut reader = BufReader::new(DeadlineReader {
+        stream: stream.try_clone().map_err(io_error)?,
+        deadline,
+    });
+    let mut head = vec![];
+    loop {
+        let mut line = vec![];
+        (&mut reader)
+            .take((MAX_HEADERS + 1 - head.len()) as u64)
+            .read_until(b'\n', &mut line)
+            .map_err(io_error)?;
+        head.extend_from_slice(&line);
+        if head.len() > MAX_HEADERS {
+            return Err(Response::error(431, "headers too large"));
+        }
+        if line.is_empty() || line == b"\r\n" || line == b"\n" {
+            break;
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key), percent_decode(value))
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut length = None;
+    let mut expect_continue = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                length = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| Response::error(400, "invalid Content-Length"))?,
+                )
+            }
+            "content-type" => request.content_type = value.to_ascii_lowercase(),
+            "transfer-encoding" => {
+                return Err(Response::error(411, "send a Content-Length instead"))
+            }
+            "expect" => expect_continue = value.eq_ignore_ascii_case("100-continue"),
+            _ => {}
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > config.max_body {
+        return Err(Response::error(
+            413,
+            &format!("bodies are limited to {} bytes", config.max_body),
+        ));
+    }
+    if expect_continue {
+        stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .map_err(io_error)?;
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).map_err(io_error)?;
+    Ok(request)
+}
+
+/// The maze and the options of a request. The body is either the maze file, with the options
+/// in the query, or a JSON object with the maze under `maze` as a string or an array of lines
+/// next to the options. Query options win over JSON ones
+struct Input {
+    maze: Result<Labyrinth, String>,
+    json: Option<Json>,
+    query: Vec<(String, String)>,
+}
+
+impl Input {
+    fn new(request: &Request, config: &Config) -> Result<Self, Response> {
+        let text = std::str::from_utf8(&request.body)
+            .map_err(|_| Response::error(400, "the body isn't UTF-8"))?;
+        let is_json = request.content_type.starts_with("application/json")
+            || text.trim_start().starts_with('{');
+        let (maze, json) = match is_json {
+            true => {
+                let json = json::parse(text).map_err(|e| Response::error(400, &e))?;
+                let maze = match json.get("maze") {
+                    Some(Json::String(maze)) => maze.parse(),
+                    Some(Json::Array(lines)) => lines
+                        .iter()
+                        .map(|line| line.as_str().ok_or("maze lines must be strings"))
+                        .collect::<Result<Vec<&str>, _>>()
+                        .map_err(String::from)
+                        .and_then(|lines| lines.join("\n").parse()),
+                    _ => Err(String::from("maze missing, send it as a string or lines")),
+                };
+                (maze, Some(json))
+            }
+            false => (text.parse(), None),
+        };
+        let maze = maze.and_then(|lab: Labyrinth| match lab.0.len() * COLUMN_SIZE {
+            0 => Err(String::from("the maze has no fields")),
+            n if n > config.max_fields => Err(format!(
+                "the maze has {n} fields, at most {} are allowed",
+                config.max_fields
+            )),
+            _ => Ok(lab),
+        });
+        Ok(Input {
+            maze,
+            json,
+            query: request.query.clone(),
+        })
+    }
+
+    fn maze(&self) -> Result<&Labyrinth, Response> {
+        self.maze.as_ref().map_err(|e| Response::error(422, e))
+    }
+
+    /// The option as text, JSON arrays become comma separated
+    fn option(&self, key: &str) -> Option<String> {
+        if let Some((_, value)) = self.query.iter().rev().find(|(k, _)| k == key) {
+            return Some(value.clone());
+        }
+        let text = |value: &Json| match value {
+            Json::String(s) => Some(s.clone()),
+            Json::Number(n) => Some(n.to_string()),
+            Json::Bool(b) => Some(b.to_string()),
+            _ => None,
+        };
+        match self.json.as_ref()?.get(key)? {
+            Json::Array(items) => Some(
+                items
+                    .iter()
+                    .map(|item| text(item).unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            value => text(value),
+        }
+    }
+
+    fn flag(&self, key: &str) -> Result<bool, Response> {
+        match self.option(key).as_deref() {
+            None | Some("false" | "0") => Ok(false),
+            Some("" | "true" | "1") => Ok(true),
+            Some(other) => Err(Response::error(
+                400,
+                &format!("{key} must be true or false, not {other}"),
+            )),
+        }
+    }
+
+    /// Comma separated fields, each inside the maze
+    fn fields(&self, key: &str, lab: &Labyrinth) -> Result<Option<Vec<usize>>, Response> {
+        let Some(text) = self.option(key) else {
+            return Ok(None);
+        };
+        let fields = text
+            .split(',')
+            .filter(|f| !f.trim().is_empty())
+            .map(|f| f.trim().parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| Response::error(400, &format!("{key} must be field numbers")))?;
+        let len = lab.0.len() * COLUMN_SIZE;
+        match fields.iter().find(|f| **f >= len) {
+            Some(f) => Err(Response::error(
+                422,
+                &format!("{key} has field {f}, the maze has {len}"),
+            )),
+            None => Ok(Some(fields)),
+        }
+    }
+
+    fn problem(&self, lab: &Labyrinth, config: &Config) -> Result<Problem, Response> {
+        let start = match self.fields("start", lab)?.as_deref() {
+            None | Some([]) => 0,
+            Some([start]) => *start,
+            Some(_) => return Err(Response::error(400, "start must be a single field")),
+        };
+        let timeout = match self.option("timeout_ms") {
+            Some(ms) => Duration::from_millis(
+                ms.parse()
+                    .map_err(|_| Response::error(400, "timeout_ms must be milliseconds"))?,
+            )
+            .min(config.timeout),
+            None => config.timeout,
+        };
+        Ok(Problem {
+            start,
+            goals: self.fields("goals", lab)?.unwrap_or_default(),
+            rules: Rules {
+                ignore_doors: self.flag("ignore_doors")?,
+            },
+            limits: Limits {
+                budget: Some(Budget::new(config.max_nodes)),
+                ..Limits::timeout(timeout)
+            },
+        })
+    }
+}
+
+/// What's wrong with a maze that still parses: sides of an edge that don't agree, passages
+/// onto the border other than the exits of end fields, one-way bits without a passage and a
+/// missing end
+pub fn problems(lab: &Labyrinth) -> Vec<String> {
+    let mut problems = vec![];
+    let rows = lab.0.len();
+    let bits = |(path, door): (bool, bool)| format!("path {} door {}", path as u8, door as u8);
+    for (row, line) in lab.0.iter().enumerate() {
+        for (col, field) in line.iter().enumerate() {
+            let index = row * COLUMN_SIZE + col;
+            let (paths, doors) = (field.paths, field.doors);
+            let border = [
+                ("west", col == 0, paths.west || doors.west),
+                ("east", col + 1 == COLUMN_SIZE, paths.east || doors.east),
+                ("north", row == 0, paths.north || doors.north),
+                ("south", row + 1 == rows, paths.south || doors.south),
+            ];
+            for (side, _, _) in border.iter().filter(|(_, at, open)| *at && *open) {
+                if !field.is_end {
+                    problems.push(format!("field {index} opens {side} onto the border"));
+                }
+            }
+            let mut neighbours = vec![];
+            if col + 1 < COLUMN_SIZE {
+                let east = &line[col + 1];
+                neighbours.push((
+                    index + 1,
+                    lab.east_edge(row, col),
+                    (paths.east, doors.east, field.one_way.east),
+                    (east.paths.west, east.doors.west, east.one_way.west),
+                ));
+            }
+            if row + 1 < rows {
+                let south = &lab.0[row + 1][col];
+                neighbours.push((
+                    index + COLUMN_SIZE,
+                    lab.south_edge(row, col),
+                    (paths.south, doors.south, field.one_way.south),
+                    (south.paths.north, south.doors.north, south.one_way.north),
+                ));
+            }
+            for (other, edge, (path, door, one_way), (other_path, other_door, other_one_way)) in
+                neighbours
+            {
+                if edge != Edge::Wall {
+                    continue;
+                }
+                if path || door || other_path || other_door {
+                    problems.push(format!(
+                        "fields {index} ({}) and {other} ({}) don't agree, the edge is a wall",
+                        bits((path, door)),
+                        bits((other_path, other_door))
+                    ));
+                } else if one_way || other_one_way {
+                    problems.push(format!(
+                        "one-way bit between fields {index} and {other} without a passage"
+                    ));
+                }
+            }
+        }
+    }
+    if !lab.0.iter().flatten().any(|f| f.is_end) {
+        problems.push(String::from("no field is an end"));
+    }
+    problems
+}
+
+fn solve(input: &Input, config: &Config) -> Result<Response, Response> {
+    let lab = input.maze()?;
+    let problem = input.problem(lab, config)?;
+    let name = input
+        .option("solver")
+        .unwrap_or_else(|| String::from("bfs"));
+    // every request already runs on its own worker
+    let parallel = ParallelConfig {
+        threads: 1,
+        ..Default::default()
+    };
+    let solver = solver::by_name(&name, &parallel).ok_or_else(|| {
+        Response::error(
+            400,
+            &format!("unknown solver {name}, try one of {:?}", solver::NAMES),
+        )
+    })?;
+    Ok(Response::json(200, solver.solve(lab, &problem).to_json()))
+}
+
+fn validate(input: &Input) -> Result<Response, Response> {
+    let lab = match &input.maze {
+        Ok(lab) => lab,
+        Err(e) => {
+            return Ok(Response::json(
+                200,
+                format!("{{\"valid\":false,\"problems\":[\"{}\"]}}", json::escape(e)),
+            ))
+        }
+    };
+    let problems: Vec<String> = problems(lab)
+        .iter()
+        .map(|p| format!("\"{}\"", json::escape(p)))
+        .collect();
+    let fields = || lab.0.iter().flatten().enumerate();
+    let ends: Vec<usize> = fields().filter(|(_, f)| f.is_end).map(|(i, _)| i).collect();
+    let keys: Vec<usize> = fields()
+        .filter(|(_, f)| f.contains_key)
+        .map(|(i, _)| i)
+        .collect();
+    let walk = match input.fields("walk", lab)? {
+        Some(walk) => {
+            let rules = Rules {
+                ignore_doors: input.flag("ignore_doors")?,
+            };
+            match lab.replay(&walk, &rules) {
+                Ok(trace) => format!(",\"walk\":{{\"valid\":true,\"trace\":{}}}", trace.to_json()),
+                Err(violation) => format!(
+                    ",\"walk\":{{\"valid\":false,\"violation\":\"{}\"}}",
+                    json::escape(&violation.to_string())
+                ),
+            }
+        }
+        None => String::new(),
+    };
+    Ok(Response::json(
+        200,
+        format!(
+            "{{\"valid\":{},\"rows\":{},\"fields\":{},\"ends\":{ends:?},\"keys\":{keys:?},\"problems\":[{}]{walk}}}",
+            problems.is_empty(),
+            lab.0.len(),
+            lab.0.len() * COLUMN_SIZE,
+            problems.join(",")
+        ),
+    ))
+}
+
+fn render(input: &Input, config: &Config) -> Result<Response, Response> {
+    let lab = input.maze()?;
+    let walk = match input.flag("solution")? {
+        true => solver::BfsSolver
+            .solve(lab, &input.problem(lab, config)?)
+            .outcome
+            .into_inner()
+            .map(|s| s.walk),
+        false => None,
+    };
+    let format = input
+        .option("format")
+        .unwrap_or_else(|| String::from("box"));
+    let (content_type, body) = match format.as_str() {
+        "svg" => (
+            "image/svg+xml",
+            svg::to_svg(lab, walk.as_deref()).into_bytes(),
+        ),
+        "dot" => (
+            "text/vnd.graphviz",
+            crate::dot::to_dot(lab, walk.as_deref()).into_bytes(),
+        ),
+        "png" | "ppm" => {
+            let image = raster::render(lab, walk.as_deref(), raster::Scale::default());
+            let mut bytes = vec![];
+            match format.as_str() {
+                "png" => image.write_png(&mut bytes),
+                _ => image.write_ppm(&mut bytes),
+            }
+            .map_err(|e| Response::error(500, &e.to_string()))?;
+            match format.as_str() {
+                "png" => ("image/png", bytes),
+                _ => ("image/x-portable-pixmap", bytes),
+            }
+        }
+        style => {
+            let indices = input.option("indices").is_none() || input.flag("indices")?;
+            let renderer = render::by_name(style, indices, None).ok_or_else(|| {
+                Response::error(
+                    400,
+                    &format!(
+                        "unknown format {style}, try svg, dot, png, ppm or one of {:?}",
+                        render::NAMES
+                    ),
+                )
+            })?;
+            (
+                "text/plain; charset=utf-8",
+                renderer.to_string(lab).into_bytes(),
+            )
+        }
+    };
+    Ok(Response {
+        status: 200,
+        content_type,
+        body,
+    })
+}
+
+/// Answers a request that has been read completely
+pub fn handle(request: &Request, config: &Config) -> Response {
+    let route =
+        |input: fn(&Input, &Config) -> Result<Response, Response>| match request.method.as_str() {
+            "POST" => Input::new(request, config)
+                .and_then(|i| input(&i, config))
+                .unwrap_or_else(|e| e),
+            _ => Response::error(405, "use POST with the maze in the body"),
+        };
+    match request.path.as_str() {
+        "/health" => match request.method.as_str() {
+            "GET" => Response::json(200, String::from("{\"status\":\"ok\"}")),
+            _ => Response::error(405, "use GET"),
+        },
+        "/solve" => route(solve),
+        "/validate" => route(|input, _| validate(input)),
+        "/render" => route(render),
+        _ => Response::error(404, "try /solve, /validate, /render or /health"),
+    }
+}
+
+fn serve_connection(mut stream: TcpStream, config: &Config) -> io::Result<()> {
+    let now = Instant::now();
+    stream.set_write_timeout(Some(config.io_timeout))?;
+    let (request, response) = match read_request(&mut stream, now + config.io_timeout, config) {
+        Ok(request) => {
+            let response = panic::catch_unwind(AssertUnwindSafe(|| handle(&request, config)))
+                .unwrap_or_else(|_| Response::error(500, "the request crashed the handler"));
+            (request, response)
+        }
+        Err(response) => (Request::default(), response),
+    };
+    eprintln!(
+        "{} {} {} {:?}",
+        request.method,
+        request.path,
+        response.status,
+        now.elapsed()
+    );
+    response.write_to(&mut stream)
+}
+
+/// Serves until the process is killed, `config.workers` threads accept connections from the
+/// same socket and answer one request each
+pub fn serve(config: Config) -> io::Result<()> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port))?;
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    serve_on(listener, config)
+}
+
+pub fn serve_on(listener: TcpListener, config: Config) -> io::Result<()> {
+    let config = Arc::new(config);
+    let workers = (0..config.workers.max(1))
+        .map(|_| {
+            let listener = listener.try_clone()?;
+            let config = Arc::clone(&config);
+            Ok(thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let result = stream.and_then(|stream| serve_connection(stream, &config));
+                    if let Err(e) = result {
+                        eprintln!("connection failed: {e}");
+                    }
+                }
+            }))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(path: &str, query: &[(&str, &str)], content_type: &str, body: &str) -> Response {
+        let request = Request {
+            method: String::from("POST"),
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            content_type: content_type.to_string(),
+            body: body.as_bytes().to_vec(),
+        };
+        handle(&request, &Config::default())
+    }
+
+    fn text(response: &Response) -> &str {
+        std::str::from_utf8(&response.body).unwrap()
+    }
+
+    #[test]
+    fn solves_text_and_json() {
+        let maze = std::fs::read_to_string("./labyrinth.txt").unwrap();
+        let response = post("/solve", &[("goals", "47")], "text/plain", &maze);
+        assert_eq!(response.status, 200, "{}", text(&response));
+        assert!(text(&response)
+            .starts_with("{\"solver\":\"bfs\",\"partial\":false,\"solution\":{\"walk\":[0,"));
+        let lines: Vec<String> = maze.lines().map(|l| format!("\"{l}\"")).collect();
+        let body = format!(
+            "{{\"maze\":[{}],\"solver\":\"parallel\",\"goals\":[47]}}",
+            lines.join(",")
+        );
+        let parallel = post("/solve", &[], "application/json", &body);
+        assert_eq!(parallel.status, 200, "{}", text(&parallel));
+        let cost = |r: &Response| text(r).split("\"cost\":").nth(1).unwrap()[..3].to_string();
+        assert_eq!(cost(&parallel), cost(&response));
+
+        assert_eq!(post("/solve", &[("solver", "x")], "", &maze).status, 400);
+        assert_eq!(post("/solve", &[("start", "54")], "", &maze).status, 422);
+        assert_eq!(post("/solve", &[], "", "0000").status, 422);
+        assert_eq!(
+            post("/solve", &[], "application/json", "{\"maze\":").status,
+            400
+        );
+    }
+
+    #[test]
+    fn validates_and_renders() {
+        let maze = std::fs::read_to_string("./labyrinth.txt").unwrap();
+        let response = post("/validate", &[("walk", "0,9,10")], "", &maze);
+        assert_eq!(response.status, 200);
+        assert!(text(&response).starts_with("{\"valid\":true"));
+        assert!(text(&response).contains("\"walk\":{\"valid\":true"));
+        let broken = maze.replacen("0101 0100 0000", "0111 0100 0000", 1);
+        let response = post("/validate", &[], "", &broken);
+        assert!(text(&response).starts_with("{\"valid\":false"));
+        assert!(text(&response).contains("field 0 opens north onto the border"));
+        let response = post("/validate", &[], "", "nonsense");
+        assert!(text(&response).contains("line 1 isn't a field"));
+
+        let svg = post("/render", &[("format", "svg"), ("solution", "")], "", &maze);
+        assert_eq!(svg.content_type, "image/svg+xml");
+        assert!(text(&svg).contains("<polyline"));
+        let png = post("/render", &[("format", "png")], "", &maze);
+        assert!(png.body.starts_with(b"\x89PNG"));
+        let ascii = post(
+            "/render",
+            &[("format", "ascii"), ("indices", "false")],
+            "",
+            &maze,
+        );
+        assert!(text(&ascii).starts_with("+---+"));
+        assert_eq!(post("/render", &[("format", "gif")], "", &maze).status, 400);
+    }
+
+    #[test]
+    fn routes_and_limits() {
+        let get = |path: &str| {
+            let request = Request {
+                method: String::from("GET"),
+                path: path.to_string(),
+                ..Default::default()
+            };
+            handle(&request, &Config::default()).status
+        };
+        assert_eq!(
+            (get("/health"), get("/solve"), get("/nope")),
+            (200, 405, 404)
+        );
+        let big = "0000 0000 0000\n".repeat(COLUMN_SIZE * 300);
+        let response = post("/solve", &[], "", &big);
+        assert_eq!(response.status, 422);
+        assert!(text(&response).contains("at most 2500"));
+        assert_eq!(percent_decode("0%2C9+x%zz"), "0,9 x%zz");
+    }
+
+    #[test]
+    fn solves_stop_at_the_node_budget() {
+        let maze = std::fs::read_to_string("./labyrinth.txt").unwrap();
+        let request = Request {
+            method: String::from("POST"),
+            path: String::from("/solve"),
+            body: maze.into_bytes(),
+            ..Default::default()
+        };
+        let config = Config {
+            max_nodes: 5,
+            ..Default::default()
+        };
+        let response = handle(&request, &config);
+        assert_eq!(response.status, 200);
+        assert!(text(&response).contains("\"partial\":true"));
+        assert!(text(&response).contains("\"expanded\":5,"), "{}", text(&response));
+    }
+
+    /// Serves `config` on a free port for the rest of the test run
+    fn start(config: Config) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_on(listener, config));
+        address
+    }
+
+    /// Everything the server answers until it closes the connection
+    fn answer(stream: &mut TcpStream) -> String {
+        let mut answer = vec![];
+        let _ = stream.read_to_end(&mut answer); // the close may come as a reset
+        String::from_utf8_lossy(&answer).into_owned()
+    }
+
+    fn exchange(address: std::net::SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request).unwrap();
+        answer(&mut stream)
+    }
+
+    #[test]
+    fn serves_over_tcp() {
+        let address = start(Config {
+            workers: 2,
+            max_body: 4096,
+            ..Default::default()
+        });
+        let head = |answer: String| answer.lines().next().unwrap_or_default().to_string();
+        assert_eq!(
+            head(exchange(address, b"GET /health HTTP/1.1\r\n\r\n")),
+            "HTTP/1.1 200 OK"
+        );
+        let mut huge = b"GET /health HTTP/1.1\r\nX: ".to_vec();
+        huge.resize(MAX_HEADERS + 1, b'x');
+        assert_eq!(
+            head(exchange(address, &huge)),
+            "HTTP/1.1 431 Request Header Fields Too Large"
+        );
+        assert_eq!(
+            head(exchange(
+                address,
+                b"POST /solve HTTP/1.1\r\nContent-Length: 4097\r\n\r\n"
+            )),
+            "HTTP/1.1 413 Payload Too Large"
+        );
+        assert_eq!(
+            head(exchange(
+                address,
+                b"POST /solve HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"
+            )),
+            "HTTP/1.1 411 Length Required"
+        );
+        assert_eq!(
+            head(exchange(
+                address,
+                b"POST /solve HTTP/1.1\r\nContent-Length: 12x\r\n\r\n"
+            )),
+            "HTTP/1.1 400 Bad Request"
+        );
+
+        let maze = std::fs::read_to_string("./labyrinth.txt").unwrap();
+        let body = format!("{{\"maze\":{:?},\"goals\":[47]}}", maze);
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST /solve HTTP/1.1\r\ncontent-length: {}\r\nExpect: 100-continue\r\n\r\n",
+            body.len()
+        )
+        .unwrap();
+        let mut continue_line = [0; 25];
+        stream.read_exact(&mut continue_line).unwrap();
+        assert_eq!(&continue_line, b"HTTP/1.1 100 Continue\r\n\r\n");
+        stream.write_all(body.as_bytes()).unwrap();
+        let answer = answer(&mut stream);
+        assert!(answer.starts_with("HTTP/1.1 200 OK\r\n"), "{answer}");
+        assert!(answer.contains("\"cost\":9,"), "{answer}");
+    }
+
+    #[test]
+    fn slow_requests_time_out_as_a_whole() {
+        let io_timeout = Duration::from_millis(300);
+        let address = start(Config {
+            workers: 1,
+            io_timeout,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        // a byte every 50ms never trips a per-read timeout
+        thread::spawn(move || {
+            for byte in b"GET /health HTTP/1.1\r\nX: "
+                .iter()
+                .chain([b'x'; 100].iter())
+            {
+                if writer.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let answer = answer(&mut stream);
+        assert!(
+            answer.starts_with("HTTP/1.1 408 Request Timeout"),
+            "{answer}"
+        );
+        assert!(now.elapsed() < io_timeout * 4, "{:?}", now.elapsed());
+    }
+}
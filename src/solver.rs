@@ -0,0 +1,290 @@
+use crate::{
+    clock::Instant,
+    pathfinder::{self, Limits, Outcome, ParallelConfig, SearchStats},
+    replay::{replay, Trace},
+    Labyrinth,
+};
+use std::{fmt, time::Duration};
+
+pub const NAMES: [&str; 3] = ["greedy", "parallel", "bfs"];
+
+/// What walks are allowed to do
+#[derive(Debug, Default, Clone)]
+pub struct Rules {
+    pub ignore_doors: bool, // doors open without keys
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Problem {
+    pub start: usize,
+    pub goals: Vec<usize>, // every end field when empty
+    pub rules: Rules,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub elapsed: Duration,
+    pub search: SearchStats,
+}
+
+impl Stats {
+    pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"elapsed_us\":{},\"expanded\":{},\"pruned\":{},\"peak_open\":{},",
+                "\"keys_considered\":{},\"doors_considered\":{},\"per_thread\":{:?}}}"
+            ),
+            self.elapsed.as_micros(),
+            self.search.expanded,
+            self.search.pruned,
+            self.search.peak_open,
+            self.search.keys_considered,
+            self.search.doors_considered,
+            self.search.per_thread,
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "elapsed           {:?}", self.elapsed)?;
+        writeln!(f, "expanded          {}", self.search.expanded)?;
+        writeln!(f, "pruned            {}", self.search.pruned)?;
+        writeln!(f, "peak open         {}", self.search.peak_open)?;
+        writeln!(f, "keys considered   {}", self.search.keys_considered)?;
+        writeln!(f, "doors considered  {}", self.search.doors_considered)?;
+        if !self.search.per_thread.is_empty() {
+            writeln!(f, "per thread        {:?}", self.search.per_thread)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub walk: Vec<usize>,
+    pub cost: usize,                // moves made
+    pub keys: Vec<usize>,           // fields the keys were picked up from
+    pub doors: Vec<(usize, usize)>, // doors opened, in the order of the walk
+}
+
+impl Solution {
+    /// Keys and doors come from replaying the walk, so they follow the same rules as
+    /// [`replay`](crate::replay::replay)
+    fn new(walk: Vec<usize>, trace: Trace) -> Self {
+        Solution {
+            cost: walk.len().saturating_sub(1),
+            walk,
+            keys: trace.keys,
+            doors: trace.doors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let doors: Vec<[usize; 2]> = self.doors.iter().map(|(a, b)| [*a, *b]).collect();
+        format!(
+            "{{\"walk\":{:?},\"cost\":{},\"keys\":{:?},\"doors\":{:?}}}",
+            self.walk, self.cost, self.keys, doors
+        )
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} cost {} keys {:?} doors {:?}",
+            self.walk, self.cost, self.keys, self.doors
+        )
+    }
+}
+
+/// What a solver found and the work it took, the stats are there with or without a walk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub solver: &'static str,
+    pub outcome: Outcome<Option<Solution>>,
+    pub stats: Stats,
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        let solution = match &self.outcome {
+            Outcome::Complete(Some(s)) | Outcome::Partial(Some(s)) => s.to_json(),
+            _ => String::from("null"),
+        };
+        format!(
+            "{{\"solver\":\"{}\",\"partial\":{},\"solution\":{solution},\"stats\":{}}}",
+            self.solver,
+            self.outcome.is_partial(),
+            self.stats.to_json()
+        )
+    }
+}
+
+/// Replays a walk a solver returned, `None` unless it follows the rules from `start` and,
+/// when given, stops on `goal`
+fn check(
+    walk: &[usize],
+    start: usize,
+    goal: Option<usize>,
+    rules: &Rules,
+    graph: &[Vec<u8>],
+    keys: &[bool],
+) -> Option<Trace> {
+    let ends: Vec<bool> = (0..graph.len()).map(|f| Some(f) == goal).collect();
+    let trace = replay(walk, rules, graph, keys, &ends).ok()?;
+    (walk[0] == start && (goal.is_none() || trace.at_end)).then_some(trace)
+}
+
+pub trait Solver {
+    fn name(&self) -> &'static str;
+
+    /// Best walk from `start` to a single `goal`
+    fn solve_to(
+        &self,
+        graph: &[Vec<u8>],
+        keys: &[bool],
+        start: usize,
+        goal: usize,
+        limits: &Limits,
+        stats: &mut SearchStats,
+    ) -> Outcome<Option<Vec<usize>>>;
+
+    /// Best walk to any of the goals
+    fn solve(&self, labyrinth: &Labyrinth, problem: &Problem) -> Report {
+        let now = Instant::now();
+        let (mut graph, keys, ends) = labyrinth.pathfind_matrix();
+        if problem.rules.ignore_doors {
+            for edge in graph.iter_mut().flat_map(|row| row.iter_mut()) {
+                if *edge == 255 {
+                    *edge = 1;
+                }
+            }
+        }
+        let goals = match problem.goals.is_empty() {
+            true => (0..ends.len()).filter(|f| ends[*f]).collect(),
+            false => problem.goals.clone(),
+        };
+        let mut best: Option<(Vec<usize>, Trace)> = None;
+        let mut partial = false;
+        let mut search = SearchStats::default();
+        for goal in goals {
+            let outcome = self.solve_to(
+                &graph,
+                &keys,
+                problem.start,
+                goal,
+                &problem.limits,
+                &mut search,
+            );
+            let stopped = outcome.is_partial();
+            partial |= stopped;
+            // a walk that breaks the rules counts as no walk
+            let found = outcome.into_inner().and_then(|walk| {
+                let goal = (!stopped).then_some(goal);
+                let trace = check(&walk, problem.start, goal, &problem.rules, &graph, &keys)?;
+                Some((walk, trace))
+            });
+            if let Some((walk, trace)) = found {
+                if best.as_ref().map_or(true, |(b, _)| walk.len() < b.len()) {
+                    best = Some((walk, trace));
+                }
+            }
+        }
+        let solution = best.map(|(walk, trace)| Solution::new(walk, trace));
+        Report {
+            solver: self.name(),
+            outcome: match partial {
+                true => Outcome::Partial(solution),
+                false => Outcome::Complete(solution),
+            },
+            stats: Stats {
+                elapsed: now.elapsed(),
+                search,
+            },
+        }
+    }
+}
+
+/// A* towards the goal with detours to the closest keys, fast but not always optimal. The
+/// detours miscount keys at times, a walk that doesn't replay is searched again breadth first
+pub struct GreedySolver;
+
+impl Solver for GreedySolver {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+    fn solve_to(
+        &self,
+        graph: &[Vec<u8>],
+        keys: &[bool],
+        start: usize,
+        goal: usize,
+        limits: &Limits,
+        stats: &mut SearchStats,
+    ) -> Outcome<Option<Vec<usize>>> {
+        let legal = |walk: &[usize], goal| {
+            check(walk, start, goal, &Rules::default(), graph, keys).is_some()
+        };
+        match pathfinder::greedy_pathfind(start, goal, graph, keys, limits, stats, None) {
+            Outcome::Complete(Some(walk)) if !legal(&walk, Some(goal)) => {
+                pathfinder::optimal_walks(start, goal, graph, keys, limits, stats, None)
+                    .map(|mut walks| walks.next())
+            }
+            Outcome::Partial(Some(walk)) if !legal(&walk, None) => Outcome::Partial(None),
+            outcome => outcome,
+        }
+    }
+}
+
+/// Parallel branch and bound over walks, optimal
+pub struct ParallelSolver(pub ParallelConfig);
+
+impl Solver for ParallelSolver {
+    fn name(&self) -> &'static str {
+        "parallel"
+    }
+    fn solve_to(
+        &self,
+        graph: &[Vec<u8>],
+        keys: &[bool],
+        start: usize,
+        goal: usize,
+        limits: &Limits,
+        stats: &mut SearchStats,
+    ) -> Outcome<Option<Vec<usize>>> {
+        pathfinder::parallel_backtrack(start, goal, graph, keys, &self.0, limits, stats, None)
+    }
+}
+
+/// Breadth first search over fields and inventories, optimal
+pub struct BfsSolver;
+
+impl Solver for BfsSolver {
+    fn name(&self) -> &'static str {
+        "bfs"
+    }
+    fn solve_to(
+        &self,
+        graph: &[Vec<u8>],
+        keys: &[bool],
+        start: usize,
+        goal: usize,
+        limits: &Limits,
+        stats: &mut SearchStats,
+    ) -> Outcome<Option<Vec<usize>>> {
+        pathfinder::optimal_walks(start, goal, graph, keys, limits, stats, None)
+            .map(|mut walks| walks.next())
+    }
+}
+
+pub fn by_name(name: &str, config: &ParallelConfig) -> Option<Box<dyn Solver>> {
+    match name {
+        "greedy" => Some(Box::new(GreedySolver)),
+        "parallel" => Some(Box::new(ParallelSolver(config.clone()))),
+        "bfs" => Some(Box::new(BfsSolver)),
+        _ => None,
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{Labyrinth, COLUMN_SIZE};
+use std::fmt::Write;
+
+const CELL: usize = 40;
+const MARGIN: usize = 10;
+
+const STYLE: &str = concat!(
+    ".wall{stroke:#222;stroke-width:3;stroke-linecap:round}",
+    ".door{stroke:#a0522d;stroke-width:7}",
+    ".empty{fill:#ddd}",
+    ".key{fill:#e6b800;stroke:#8a6d00}",
+    ".end{fill:#d22}",
+    ".index{font:9px sans-serif;fill:#888}",
+    ".walk{fill:none;stroke:#1e6fd9;stroke-width:3;stroke-opacity:.7;stroke-linejoin:round}",
+    ".step{font:bold 9px sans-serif;fill:#1e6fd9}",
+);
+
+fn center(index: usize) -> (usize, usize) {
+    let (row, col) = (index / COLUMN_SIZE, index % COLUMN_SIZE);
+    (
+        MARGIN + col * CELL + CELL / 2,
+        MARGIN + row * CELL + CELL / 2,
+    )
+}
+
+/// Draws the maze, and the walk when given as a polyline with the step numbers on every field
+/// it passes. Walls and doors are drawn per field, a side either field closes shows as a wall
+pub fn to_svg(lab: &Labyrinth, walk: Option<&[usize]>) -> String {
+    let rows = lab.0.len();
+    let (width, height) = (COLUMN_SIZE * CELL + 2 * MARGIN, rows * CELL + 2 * MARGIN);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    );
+    let _ = writeln!(svg, "<style>{STYLE}</style>");
+    let _ = writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>");
+    for (row, line) in lab.0.iter().enumerate() {
+        for (col, field) in line.iter().enumerate() {
+            let index = row * COLUMN_SIZE + col;
+            let (x, y) = (MARGIN + col * CELL, MARGIN + row * CELL);
+            if field.is_empty() {
+                let _ = writeln!(
+                    svg,
+                    "<rect class=\"empty\" x=\"{x}\" y=\"{y}\" width=\"{CELL}\" height=\"{CELL}\"/>"
+                );
+                continue;
+            }
+            let sides = [
+                (field.paths.north, field.doors.north, (x, y), (x + CELL, y)),
+                (
+                    field.paths.south,
+                    field.doors.south,
+                    (x, y + CELL),
+                    (x + CELL, y + CELL),
+                ),
+                (field.paths.west, field.doors.west, (x, y), (x, y + CELL)),
+                (
+                    field.paths.east,
+                    field.doors.east,
+                    (x + CELL, y),
+                    (x + CELL, y + CELL),
+                ),
+            ];
+            for (path, door, (x1, y1), (x2, y2)) in sides {
+                if door {
+                    // the middle half of the side
+                    let (dx, dy) = ((x2 - x1) / 4, (y2 - y1) / 4);
+                    let _ = writeln!(
+                        svg,
+                        "<line class=\"door\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                        x1 + dx,
+                        y1 + dy,
+                        x2 - dx,
+                        y2 - dy
+                    );
+                } else if !path {
+                    let _ = writeln!(
+                        svg,
+                        "<line class=\"wall\" x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\"/>"
+                    );
+                }
+            }
+            let (cx, cy) = center(index);
+            if field.is_end {
+                let _ = writeln!(
+                    svg,
+                    "<polygon class=\"end\" points=\"{},{} {},{} {},{}\"><title>end</title></polygon>",
+                    cx - 6,
+                    cy - 10,
+                    cx + 10,
+                    cy - 4,
+                    cx - 6,
+                    cy + 2
+                );
+            }
+            if field.contains_key {
+                let _ = writeln!(
+                    svg,
+                    "<g class=\"key\"><title>key</title><circle cx=\"{}\" cy=\"{cy}\" r=\"5\"/><rect x=\"{}\" y=\"{}\" width=\"12\" height=\"3\"/></g>",
+                    cx - 6,
+                    cx - 1,
+                    cy - 1
+                );
+            }
+            let _ = writeln!(
+                svg,
+                "<text class=\"index\" x=\"{}\" y=\"{}\">{index}</text>",
+                x + 3,
+                y + 11
+            );
+        }
+    }
+    if let Some(walk) = walk.filter(|w| !w.is_empty()) {
+        let points: Vec<String> = walk
+            .iter()
+            .map(|f| {
+                let (x, y) = center(*f);
+                format!("{x},{y}")
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline class=\"walk\" points=\"{}\"/>",
+            points.join(" ")
+        );
+        let mut steps: Vec<(usize, Vec<String>)> = vec![];
+        for (step, field) in walk.iter().enumerate() {
+            match steps.iter_mut().find(|(f, _)| f == field) {
+                Some((_, numbers)) => numbers.push(step.to_string()),
+                None => steps.push((*field, vec![step.to_string()])),
+            }
+        }
+        for (field, numbers) in steps {
+            let (cx, cy) = center(field);
+            let _ = writeln!(
+                svg,
+                "<text class=\"step\" x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+                cx + CELL / 2 - 3,
+                cy + CELL / 2 - 3,
+                numbers.join(",")
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_every_feature() {
+        let lab = crate::read_file("./labyrinth.txt").unwrap();
+        let walk = [0, 9, 10, 19, 10];
+        let svg = to_svg(&lab, Some(&walk));
+        let count = |needle: &str| svg.matches(needle).count();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(
+            count("class=\"index\""),
+            lab.0.len() * COLUMN_SIZE - count("class=\"empty\"")
+        );
+        assert_eq!(count("<title>end</title>"), 2);
+        assert!(count("class=\"door\"") > 0 && count("<title>key</title>") > 0);
+        assert_eq!(count("<polyline"), 1);
+        assert!(svg.contains(">2,4</text>"));
+        assert!(!to_svg(&lab, None).contains("<polyline"));
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+};
+
+/// Keys the interactive modes understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    Char(char),
+}
+
+impl Key {
+    /// Arrow keys and WASD as (row, column) offsets
+    pub fn direction(self) -> Option<(isize, isize)> {
+        match self {
+            Key::Up | Key::Char('w') => Some((-1, 0)),
+            Key::Down | Key::Char('s') => Some((1, 0)),
+            Key::Left | Key::Char('a') => Some((0, -1)),
+            Key::Right | Key::Char('d') => Some((0, 1)),
+            _ => None,
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "stdin is not a terminal",
+        )),
+    }
+}
+
+/// Unbuffered, unechoed input with the cursor hidden until dropped. Reads give up after a
+/// tenth of a second so callers can animate while waiting for keys. Uses `stty`, so it needs a
+/// Unix terminal
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enter() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "0", "time", "1"])?;
+        print!("\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawMode { saved })
+    }
+
+    /// The next key pressed, `None` if nothing was pressed in time
+    pub fn read_key(&self) -> io::Result<Option<Key>> {
+        let mut stdin = io::stdin().lock();
+        let mut byte = [0u8; 1];
+        if stdin.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        let key = match byte[0] {
+            b'\n' | b'\r' => Key::Enter,
+            0x1b => {
+                let mut sequence = [0u8; 2];
+                match stdin.read(&mut sequence)? {
+                    2 if sequence[0] == b'[' => match sequence[1] {
+                        b'A' => Key::Up,
+                        b'B' => Key::Down,
+                        b'C' => Key::Right,
+                        b'D' => Key::Left,
+                        _ => return Ok(None),
+                    },
+                    _ => Key::Escape,
+                }
+            }
+            b => Key::Char(b.to_ascii_lowercase() as char),
+        };
+        Ok(Some(key))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Whether stdout goes to a terminal rather than a file or a pipe
+#[cfg(unix)]
+pub fn stdout_is_terminal() -> bool {
+    extern "C" {
+        fn isatty(fd: i32) -> i32;
+    }
+    // SAFETY: isatty only inspects the descriptor, 1 is stdout
+    unsafe { isatty(1) == 1 }
+}
+
+#[cfg(not(unix))]
+pub fn stdout_is_terminal() -> bool {
+    false
+}
+
+pub fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
+/// Moves the cursor, `row` and `col` count from 0
+pub fn move_to(row: usize, col: usize) {
+    print!("\x1b[{};{}H", row + 1, col + 1);
+}
+
+/// Prints `text` at the position and clears the rest of the line
+pub fn status_line(row: usize, text: &str) {
+    move_to(row, 0);
+    print!("{text}\x1b[K");
+}
@@ -0,0 +1,128 @@
+use std::str::FromStr;
+
+pub const NAMES: [&str; 3] = ["default", "light", "contrast"];
+
+/// What a piece of the drawing shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Wall,
+    Door,
+    Key,
+    End,
+    Walk,
+}
+
+/// ANSI SGR parameters for every part, e.g. `"1;33"` for bold yellow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub wall: String,
+    pub door: String,
+    pub key: String,
+    pub end: String,
+    pub walk: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset("default").unwrap()
+    }
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Self> {
+        let [wall, door, key, end, walk] = match name {
+            "default" => ["90", "33", "93", "91", "94"],
+            "light" => ["30", "31", "33", "35", "34"],
+            "contrast" => ["97", "1;33", "1;93", "1;91", "1;96"],
+            _ => return None,
+        };
+        Some(Theme {
+            wall: wall.to_string(),
+            door: door.to_string(),
+            key: key.to_string(),
+            end: end.to_string(),
+            walk: walk.to_string(),
+        })
+    }
+
+    pub fn paint(&self, part: Part, text: &str) -> String {
+        let code = match part {
+            Part::Wall => &self.wall,
+            Part::Door => &self.door,
+            Part::Key => &self.key,
+            Part::End => &self.end,
+            Part::Walk => &self.walk,
+        };
+        format!("\x1b[{code}m{text}\x1b[0m")
+    }
+}
+
+/// A preset name, or comma separated `part=code` pairs changing the default theme,
+/// e.g. `door=1;31,walk=92`
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(theme) = Theme::preset(s) {
+            return Ok(theme);
+        }
+        let mut theme = Theme::default();
+        for pair in s.split(',') {
+            let (part, code) = pair.split_once('=').ok_or(format!(
+                "unknown theme {s}, try one of {NAMES:?} or part=code pairs"
+            ))?;
+            if code.is_empty() || !code.chars().all(|c| c.is_ascii_digit() || c == ';') {
+                return Err(format!("{code} isn't an SGR code like 1;33"));
+            }
+            let slot = match part {
+                "wall" => &mut theme.wall,
+                "door" => &mut theme.door,
+                "key" => &mut theme.key,
+                "end" => &mut theme.end,
+                "walk" => &mut theme.walk,
+                _ => {
+                    return Err(format!(
+                        "unknown part {part}, try wall, door, key, end or walk"
+                    ))
+                }
+            };
+            *slot = code.to_string();
+        }
+        Ok(theme)
+    }
+}
+
+/// Colors for a drawing, the fields of `walk` are highlighted
+#[derive(Debug, Default, Clone)]
+pub struct Colors<'a> {
+    pub theme: Theme,
+    pub walk: &'a [usize],
+}
+
+/// Paints with the theme when there is one, leaves the text alone otherwise
+pub fn paint(theme: Option<&Theme>, part: Part, text: &str) -> String {
+    match theme {
+        Some(theme) => theme.paint(part, text),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_presets_and_overrides() {
+        assert_eq!(
+            Theme::from_str("light"),
+            Ok(Theme::preset("light").unwrap())
+        );
+        let theme = Theme::from_str("door=1;31,walk=92").unwrap();
+        assert_eq!((theme.door.as_str(), theme.walk.as_str()), ("1;31", "92"));
+        assert_eq!(theme.wall, Theme::default().wall);
+        assert!(Theme::from_str("dark").is_err());
+        assert!(Theme::from_str("floor=1").is_err());
+        assert!(Theme::from_str("door=red").is_err());
+        assert_eq!(theme.paint(Part::Door, "#"), "\x1b[1;31m#\x1b[0m");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    field_center, field_lines,
+    pathfinder::{self, Frame, Limits, ParallelConfig, Recorder, SearchStats},
+    terminal::{self, Key, RawMode},
+    Labyrinth, COLUMN_SIZE,
+};
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+pub const ALGORITHMS: [&str; 4] = ["a_star", "greedy", "bfs", "parallel"];
+
+const CURRENT: &str = "██";
+const BEST: &str = "**";
+const OPEN: &str = "()";
+const VISITED: &str = "..";
+
+/// Runs the search with frame recording on, the last frame holds the walk it returned
+pub fn record(
+    lab: &Labyrinth,
+    algorithm: &str,
+    start: usize,
+    end: usize,
+    config: &ParallelConfig,
+) -> Option<Vec<Frame>> {
+    let (graph, keys, _) = lab.pathfind_matrix();
+    let limits = Limits::default();
+    let mut stats = SearchStats::default();
+    let mut frames = vec![];
+    let recorder: Option<&mut dyn Recorder> = Some(&mut frames);
+    let walk = match algorithm {
+        "a_star" => pathfinder::a_star(start, end, &graph, &limits, &mut stats, recorder)
+            .into_inner()
+            .map(|(path, _)| path),
+        "greedy" => {
+            pathfinder::greedy_pathfind(start, end, &graph, &keys, &limits, &mut stats, recorder)
+                .into_inner()
+        }
+        "bfs" => {
+            pathfinder::optimal_walks(start, end, &graph, &keys, &limits, &mut stats, recorder)
+                .into_inner()
+                .next()
+        }
+        "parallel" => pathfinder::parallel_backtrack(
+            start, end, &graph, &keys, config, &limits, &mut stats, recorder,
+        )
+        .into_inner(),
+        _ => return None,
+    };
+    frames.push(Frame {
+        best: walk,
+        ..Default::default()
+    });
+    Some(frames)
+}
+
+/// The search as of one frame, frames are played forward and taken back one at a time
+struct View<'a> {
+    frames: &'a [Frame],
+    index: usize,
+    open: Vec<isize>, // open nodes on each field, the start is never pushed and goes below 0
+    visited: Vec<isize>, // times each field was expanded
+    bests: Vec<usize>, // frames that improved the best walk
+}
+
+impl<'a> View<'a> {
+    fn new(frames: &'a [Frame], fields: usize) -> Self {
+        let mut view = View {
+            frames,
+            index: 0,
+            open: vec![0; fields],
+            visited: vec![0; fields],
+            bests: (0..frames.len())
+                .filter(|i| frames[*i].best.is_some())
+                .collect(),
+        };
+        view.apply(0, 1);
+        view
+    }
+
+    /// Plays the frame with `sign` 1 and takes it back with -1
+    fn apply(&mut self, index: usize, sign: isize) {
+        let frame = &self.frames[index];
+        for field in &frame.pushed {
+            self.open[*field] += sign;
+        }
+        for field in frame.dropped.iter().chain(frame.current.last()) {
+            self.open[*field] -= sign;
+        }
+        if let Some(field) = frame.current.last() {
+            self.visited[*field] += sign;
+        }
+    }
+
+    fn seek(&mut self, index: usize) {
+        while self.index < index {
+            self.index += 1;
+            self.apply(self.index, 1);
+        }
+        while self.index > index {
+            self.apply(self.index, -1);
+            self.index -= 1;
+        }
+    }
+
+    fn best(&self) -> &'a [usize] {
+        let improved = self.bests.partition_point(|i| *i <= self.index);
+        match improved {
+            0 => &[],
+            n => self.frames[self.bests[n - 1]]
+                .best
+                .as_deref()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn fields(counts: &[isize]) -> impl Iterator<Item = usize> + '_ {
+        (0..counts.len()).filter(|f| counts[*f] > 0)
+    }
+
+    /// What every field shows, the walk being expanded wins over the best one, which wins
+    /// over the open set and then the visited fields
+    fn centers(&self, lab: &Labyrinth) -> Vec<&'static str> {
+        let mut centers: Vec<&str> = lab.0.iter().flatten().map(field_center).collect();
+        for field in View::fields(&self.visited) {
+            centers[field] = VISITED;
+        }
+        for field in View::fields(&self.open) {
+            centers[field] = OPEN;
+        }
+        for (fields, center) in [
+            (self.best(), BEST),
+            (&self.frames[self.index].current, CURRENT),
+        ] {
+            for field in fields {
+                centers[*field] = center;
+            }
+        }
+        centers
+    }
+}
+
+fn draw_field(lab: &Labyrinth, index: usize, center: &str) {
+    let (row, col) = (index / COLUMN_SIZE, index % COLUMN_SIZE);
+    let lines = field_lines(&lab.0[row][col], center, &index.to_string());
+    for (line, text) in lines.iter().enumerate() {
+        terminal::move_to(row * 4 + line, col * 6);
+        print!("{text}");
+    }
+}
+
+/// Plays the frames back, only redrawing the fields that changed. Space pauses, n or → steps
+/// forward, b or ← steps back, + and - change the speed and q quits
+pub fn animate(lab: &Labyrinth, frames: &[Frame], delay: Duration) -> io::Result<()> {
+    if frames.is_empty() {
+        return Ok(());
+    }
+    let status_row = lab.0.len() * 4;
+    let raw = RawMode::enter()?;
+    terminal::clear_screen();
+    let mut shown: Vec<&str> = vec![""; lab.0.len() * COLUMN_SIZE];
+    let mut view = View::new(frames, shown.len());
+    let (mut index, mut delay, mut paused) = (0, delay, false);
+    let mut last = Instant::now();
+    loop {
+        view.seek(index);
+        for (field, center) in view.centers(lab).into_iter().enumerate() {
+            if shown[field] != center {
+                draw_field(lab, field, center);
+                shown[field] = center;
+            }
+        }
+        terminal::status_line(
+            status_row,
+            &format!(
+                "frame {}/{}  open {}  visited {}  best {}  delay {:?}{}",
+                index + 1,
+                frames.len(),
+                View::fields(&view.open).count(),
+                View::fields(&view.visited).count(),
+                view.best().len(),
+                delay,
+                if paused { "  paused" } else { "" }
+            ),
+        );
+        terminal::status_line(
+            status_row + 1,
+            &format!("{CURRENT} expanding  {BEST} best  {OPEN} open  {VISITED} visited"),
+        );
+        terminal::status_line(
+            status_row + 2,
+            "space pause  n/→ step  b/← back  +/- speed  q quit",
+        );
+        io::stdout().flush()?;
+
+        match raw.read_key()? {
+            Some(Key::Char('q')) | Some(Key::Escape) => break,
+            Some(Key::Char(' ')) => paused = !paused,
+            Some(Key::Char('n')) | Some(Key::Right) => {
+                paused = true;
+                index = (index + 1).min(frames.len() - 1);
+            }
+            Some(Key::Char('b')) | Some(Key::Left) => {
+                paused = true;
+                index = index.saturating_sub(1);
+            }
+            Some(Key::Char('+')) | Some(Key::Char('=')) => {
+                delay = (delay / 2).max(Duration::from_millis(1))
+            }
+            Some(Key::Char('-')) => delay = (delay * 2).min(Duration::from_secs(5)),
+            _ => {}
+        }
+        // reads wait up to a tenth of a second, faster speeds skip frames to keep up
+        if !paused && last.elapsed() >= delay {
+            let skipped = (last.elapsed().as_micros() / delay.as_micros().max(1)) as usize;
+            index = (index + skipped.max(1)).min(frames.len() - 1);
+            last = Instant::now();
+        }
+    }
+    drop(raw);
+    terminal::move_to(status_row, 0);
+    print!("\x1b[J");
+    io::stdout().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_end_with_the_walk_found() {
+        let lab = crate::read_file("./labyrinth.txt").unwrap();
+        for algorithm in ALGORITHMS {
+            let frames = record(&lab, algorithm, 0, 47, &ParallelConfig::default()).unwrap();
+            assert!(frames.len() > 1, "{algorithm}");
+            let mut view = View::new(&frames, lab.0.len() * COLUMN_SIZE);
+            view.seek(frames.len() - 1);
+            assert_eq!(view.best().first(), Some(&0), "{algorithm}");
+            assert_eq!(view.best().last(), Some(&47), "{algorithm}");
+            assert!(view.visited[0] > 0, "{algorithm}");
+            view.seek(0);
+            assert_eq!(view.visited.iter().sum::<isize>(), 1, "{algorithm}");
+        }
+        assert!(record(&lab, "dijkstra", 0, 47, &ParallelConfig::default()).is_none());
+    }
+}
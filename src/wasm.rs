@@ -0,0 +1,295 @@
+//! The API behind `web/lavirint.mjs`. Every call takes and returns JSON text so the JS side
+//! only has to copy strings in and out of the module's memory, the exports exist on wasm32 only
+use crate::{
+    json::{self, Json},
+    pathfinder::{Limits, ParallelConfig},
+    solver::{self, Problem, Rules},
+    svg, Labyrinth, COLUMN_SIZE,
+};
+use std::time::Duration;
+
+fn error(message: &str) -> String {
+    format!("{{\"error\":\"{}\"}}", json::escape(message))
+}
+
+/// Options are a JSON object, an empty string means none
+fn options(text: &str) -> Result<Json, String> {
+    match text.trim().is_empty() {
+        true => Ok(Json::Object(vec![])),
+        false => json::parse(text),
+    }
+}
+
+fn fields(options: &Json, key: &str, lab: &Labyrinth) -> Result<Option<Vec<usize>>, String> {
+    let Some(value) = options.get(key) else {
+        return Ok(None);
+    };
+    let fields = match value {
+        Json::Array(items) => items.iter().map(Json::as_usize).collect(),
+        value => value.as_usize().map(|field| vec![field]),
+    }
+    .ok_or(format!("{key} must be field numbers"))?;
+    let len = lab.0.len() * COLUMN_SIZE;
+    match fields.iter().find(|f| **f >= len) {
+        Some(f) => Err(format!("{key} has field {f}, the maze has {len}")),
+        None => Ok(Some(fields)),
+    }
+}
+
+fn rules(options: &Json) -> Rules {
+    Rules {
+        ignore_doors: options
+            .get("ignore_doors")
+            .and_then(Json::as_bool)
+            .unwrap_or(false),
+    }
+}
+
+/// Size of the maze, its keys and ends, and every passage as its two fields and whether it's a
+/// path or a door, one-way ones name the field they can only be left from. Enough for a front
+/// end to draw the maze
+pub fn describe(lab: &Labyrinth) -> String {
+    let all = || lab.0.iter().flatten().enumerate();
+    let keys: Vec<usize> = all()
+        .filter(|(_, f)| f.contains_key)
+        .map(|(i, _)| i)
+        .collect();
+    let ends: Vec<usize> = all().filter(|(_, f)| f.is_end).map(|(i, _)| i).collect();
+    let (graph, _, _) = lab.pathfind_matrix();
+    let mut edges = vec![];
+    for (a, row) in graph.iter().enumerate() {
+        for (b, edge) in row.iter().enumerate().skip(a + 1) {
+            let back = graph[b][a];
+            let kind = match edge.max(&back) {
+                0 => continue,
+                255 => "door",
+                _ => "path",
+            };
+            let one_way = match (*edge, back) {
+                (0, _) => format!(",\"one_way\":{b}"),
+                (_, 0) => format!(",\"one_way\":{a}"),
+                _ => String::new(),
+            };
+            edges.push(format!(
+                "{{\"between\":[{a},{b}],\"kind\":\"{kind}\"{one_way}}}"
+            ));
+        }
+    }
+    format!(
+        "{{\"rows\":{},\"columns\":{COLUMN_SIZE},\"keys\":{keys:?},\"ends\":{ends:?},\"edges\":[{}]}}",
+        lab.0.len(),
+        edges.join(",")
+    )
+}
+
+/// `solver` (bfs when missing), `start`, `goals`, `ignore_doors`, `timeout_ms` and `threads`
+/// like the command line, answers like `--json`. The parallel solver runs on one thread on
+/// wasm32 whatever `threads` says
+pub fn solve(lab: &Labyrinth, options_text: &str) -> String {
+    let run = || -> Result<String, String> {
+        let options = options(options_text)?;
+        let name = options
+            .get("solver")
+            .and_then(Json::as_str)
+            .unwrap_or("bfs");
+        let mut config = ParallelConfig::default();
+        if let Some(threads) = options.get("threads").and_then(Json::as_usize) {
+            config.threads = threads;
+        }
+        let solver = solver::by_name(name, &config).ok_or(format!(
+            "unknown solver {name}, try one of {:?}",
+            solver::NAMES
+        ))?;
+        let start = match fields(&options, "start", lab)?.as_deref() {
+            None => 0,
+            Some([start]) => *start,
+            Some(_) => return Err(String::from("start must be a single field")),
+        };
+        let limits = match options.get("timeout_ms").and_then(Json::as_usize) {
+            Some(ms) => Limits::timeout(Duration::from_millis(ms as u64)),
+            None => Limits::default(),
+        };
+        let problem = Problem {
+            start,
+            goals: fields(&options, "goals", lab)?.unwrap_or_default(),
+            rules: rules(&options),
+            limits,
+        };
+        Ok(solver.solve(lab, &problem).to_json())
+    };
+    run().unwrap_or_else(|e| error(&e))
+}
+
+/// Checks `walk` move by move with `ignore_doors`, a game can send the walk so far after every
+/// move to learn whether it was allowed and what it picked up
+pub fn replay(lab: &Labyrinth, options_text: &str) -> String {
+    let run = || -> Result<String, String> {
+        let options = options(options_text)?;
+        let walk = match options.get("walk").and_then(Json::as_array) {
+            Some(walk) => walk
+                .iter()
+                .map(Json::as_usize)
+                .collect::<Option<Vec<usize>>>()
+                .ok_or("walk must be field numbers")?,
+            None => return Err(String::from("walk missing")),
+        };
+        Ok(match lab.replay(&walk, &rules(&options)) {
+            Ok(trace) => format!("{{\"valid\":true,\"trace\":{}}}", trace.to_json()),
+            Err(violation) => format!(
+                "{{\"valid\":false,\"violation\":\"{}\"}}",
+                json::escape(&violation.to_string())
+            ),
+        })
+    };
+    run().unwrap_or_else(|e| error(&e))
+}
+
+/// The SVG picture, with `walk` drawn on top
+pub fn to_svg(lab: &Labyrinth, options_text: &str) -> String {
+    let run = || -> Result<String, String> {
+        let options = options(options_text)?;
+        let walk = fields(&options, "walk", lab)?;
+        Ok(svg::to_svg(lab, walk.as_deref()))
+    };
+    run().unwrap_or_else(|e| error(&e))
+}
+
+/// Raw exports for `WebAssembly.instantiate`. Mazes live in slots addressed by handle, strings
+/// go in through `lavirint_alloc` and come out through `lavirint_output`
+#[cfg(target_arch = "wasm32")]
+mod exports {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static MAZES: RefCell<Vec<Option<Labyrinth>>> = const { RefCell::new(vec![]) };
+        static OUTPUT: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    /// Takes back a buffer handed out by `lavirint_alloc` and filled by the caller
+    ///
+    /// # Safety
+    /// `ptr` and `len` must come from `lavirint_alloc` and the buffer must not be used again
+    unsafe fn take(ptr: *mut u8, len: usize) -> String {
+        let bytes = Vec::from_raw_parts(ptr, len, len);
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Stores the text for `lavirint_output` and returns its length in bytes
+    fn output(text: String) -> usize {
+        OUTPUT.with(|output| {
+            *output.borrow_mut() = text;
+            output.borrow().len()
+        })
+    }
+
+    fn with_maze(handle: usize, f: impl FnOnce(&Labyrinth) -> String) -> usize {
+        let text = MAZES.with(|mazes| match mazes.borrow().get(handle) {
+            Some(Some(lab)) => f(lab),
+            _ => error(&format!("no maze with handle {handle}")),
+        });
+        output(text)
+    }
+
+    #[no_mangle]
+    pub extern "C" fn lavirint_alloc(len: usize) -> *mut u8 {
+        let mut buffer = Vec::<u8>::with_capacity(len);
+        let ptr = buffer.as_mut_ptr();
+        std::mem::forget(buffer);
+        ptr
+    }
+
+    #[no_mangle]
+    pub extern "C" fn lavirint_output() -> *const u8 {
+        OUTPUT.with(|output| output.borrow().as_ptr())
+    }
+
+    #[no_mangle]
+    pub extern "C" fn lavirint_output_len() -> usize {
+        OUTPUT.with(|output| output.borrow().len())
+    }
+
+    /// Parses the maze file, returns its handle or -1 with the error in the output
+    ///
+    /// # Safety
+    /// See `take`
+    #[no_mangle]
+    pub unsafe extern "C" fn lavirint_load(ptr: *mut u8, len: usize) -> i32 {
+        match take(ptr, len).parse::<Labyrinth>() {
+            Ok(lab) => MAZES.with(|mazes| {
+                let mut mazes = mazes.borrow_mut();
+                mazes.push(Some(lab));
+                mazes.len() as i32 - 1
+            }),
+            Err(e) => {
+                output(error(&e));
+                -1
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn lavirint_unload(handle: usize) {
+        MAZES.with(|mazes| {
+            if let Some(slot) = mazes.borrow_mut().get_mut(handle) {
+                *slot = None;
+            }
+        })
+    }
+
+    #[no_mangle]
+    pub extern "C" fn lavirint_describe(handle: usize) -> usize {
+        with_maze(handle, describe)
+    }
+
+    /// # Safety
+    /// See `take`
+    #[no_mangle]
+    pub unsafe extern "C" fn lavirint_solve(handle: usize, ptr: *mut u8, len: usize) -> usize {
+        let options = take(ptr, len);
+        with_maze(handle, |lab| solve(lab, &options))
+    }
+
+    /// # Safety
+    /// See `take`
+    #[no_mangle]
+    pub unsafe extern "C" fn lavirint_replay(handle: usize, ptr: *mut u8, len: usize) -> usize {
+        let options = take(ptr, len);
+        with_maze(handle, |lab| replay(lab, &options))
+    }
+
+    /// # Safety
+    /// See `take`
+    #[no_mangle]
+    pub unsafe extern "C" fn lavirint_svg(handle: usize, ptr: *mut u8, len: usize) -> usize {
+        let options = take(ptr, len);
+        with_maze(handle, |lab| to_svg(lab, &options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_in_json() {
+        let lab = crate::read_file("./labyrinth.txt").unwrap();
+        let bfs = solve(&lab, r#"{"goals": [47]}"#);
+        assert!(bfs.starts_with(
+            "{\"solver\":\"bfs\",\"partial\":false,\"solution\":{\"walk\":[0, 9, 10,"
+        ));
+        let parallel = solve(&lab, r#"{"solver": "parallel", "threads": 1, "goals": 47}"#);
+        assert!(parallel.contains("\"cost\":9,"), "{parallel}");
+        assert!(solve(&lab, r#"{"start": 54}"#).starts_with("{\"error\":\"start has field 54"));
+        assert!(solve(&lab, "{").starts_with("{\"error\""));
+
+        let walk = replay(&lab, r#"{"walk": [0, 9, 10, 19]}"#);
+        assert!(walk.contains("\"picked_up_key\":true"), "{walk}");
+        assert!(replay(&lab, r#"{"walk": [0, 2]}"#).starts_with("{\"valid\":false"));
+        assert!(to_svg(&lab, r#"{"walk": [0, 9]}"#).contains("<polyline"));
+
+        let description = describe(&lab);
+        assert!(description.starts_with("{\"rows\":6,\"columns\":9,\"keys\":[2, 15,"));
+        assert!(description.contains("{\"between\":[0,1],\"kind\":\"door\"}"));
+    }
+}
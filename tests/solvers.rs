@@ -0,0 +1,282 @@
+use lavirint::{
+    generate::{generate, Params},
+    pathfinder::{Limits, Outcome, ParallelConfig, SearchStats},
+    replay::Violation,
+    solver::{BfsSolver, GreedySolver, ParallelSolver, Problem, Rules, Solution, Solver},
+    Labyrinth,
+};
+use proptest::prelude::*;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+fn maze(seed: u64, rows: usize) -> Labyrinth {
+    generate(&Params {
+        rows,
+        keys: 0.3,
+        doors: 0.2,
+        loops: 0.3,
+        seed,
+    })
+}
+
+fn problem() -> Problem {
+    Problem::default() // from field 0 to any end
+}
+
+fn solvers() -> Vec<Box<dyn Solver>> {
+    vec![
+        Box::new(GreedySolver),
+        Box::new(BfsSolver),
+        Box::new(ParallelSolver(ParallelConfig {
+            threads: 2,
+            deterministic: false,
+        })),
+    ]
+}
+
+/// Replays the solution and checks it starts at 0 and ends on an end field
+fn check_walk(labyrinth: &Labyrinth, solution: &Solution) -> Result<(), String> {
+    let trace = labyrinth
+        .replay(&solution.walk, &Rules::default())
+        .map_err(|v| format!("{:?}: {v}", solution.walk))?;
+    if solution.walk[0] != 0 || !trace.at_end {
+        return Err(format!("{:?} doesn't go from 0 to an end", solution.walk));
+    }
+    if trace.keys != solution.keys || trace.doors != solution.doors {
+        return Err(format!("{solution} disagrees with its replay"));
+    }
+    Ok(())
+}
+
+/// Exhaustive breadth first search over fields, keys picked up and doors opened
+fn brute_force(labyrinth: &Labyrinth) -> Option<usize> {
+    type Inventory = (usize, BTreeSet<usize>, BTreeSet<(usize, usize)>);
+    let (graph, keys, ends) = labyrinth.pathfind_matrix();
+    let start: Inventory = (0, BTreeSet::new(), BTreeSet::new());
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([(start, 1)]);
+    while let Some(((field, picked_up, opened), len)) = queue.pop_front() {
+        if ends[field] {
+            return Some(len);
+        }
+        for next in 0..graph.len() {
+            let (mut picked_up, mut opened) = (picked_up.clone(), opened.clone());
+            let door = (field.min(next), field.max(next));
+            match graph[field][next] {
+                0 => continue,
+                255 if !opened.contains(&door) => {
+                    if picked_up.len() <= opened.len() {
+                        continue;
+                    }
+                    opened.insert(door);
+                }
+                _ => {}
+            }
+            if keys[next] {
+                picked_up.insert(next);
+            }
+            let state = (next, picked_up, opened);
+            if seen.insert(state.clone()) {
+                queue.push_back((state, len + 1));
+            }
+        }
+    }
+    None
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn every_walk_is_valid(seed in any::<u64>(), rows in 1..4usize) {
+        let labyrinth = maze(seed, rows);
+        for solver in solvers() {
+            if let Some(solution) = solver.solve(&labyrinth, &problem()).outcome.into_inner() {
+                prop_assert_eq!(check_walk(&labyrinth, &solution), Ok(()), "{}", solver.name());
+            }
+        }
+    }
+
+    #[test]
+    fn exact_solvers_match_brute_force(seed in any::<u64>(), rows in 1..4usize) {
+        let labyrinth = maze(seed, rows);
+        let expected = brute_force(&labyrinth);
+        for solver in solvers().into_iter().filter(|s| s.name() != "greedy") {
+            let found = solver.solve(&labyrinth, &problem()).outcome.into_inner();
+            prop_assert_eq!(found.map(|s| s.walk.len()), expected, "{}", solver.name());
+        }
+    }
+}
+
+#[test]
+fn generated_mazes_have_doors() {
+    let doors = (0..64)
+        .filter(|seed| {
+            let (graph, _, _) = maze(*seed, 3).pathfind_matrix();
+            graph.iter().flatten().any(|edge| *edge == 255)
+        })
+        .count();
+    assert!(doors > 48, "{doors} of 64");
+}
+
+/// Greedy counts the key on the start field and walks 0 - 1 = 2 through the door without a
+/// key in hand, the solver has to search again and fetch the key below 0 first
+#[test]
+fn greedy_walk_through_a_locked_door_is_searched_again() {
+    let mut lines = vec!["0101 0000 1100", "1100 0100 0000", "1000 0000 0011"];
+    lines.extend(["0000 0000 0000"; 6]);
+    lines.push("0010 0000 1100");
+    lines.extend(["0000 0000 0000"; 8]);
+    let labyrinth: Labyrinth = lines.join("\n").parse().unwrap();
+    let walk = labyrinth.pathfind(0, 2, &Limits::default()).into_inner();
+    assert_eq!(walk, Some(vec![0, 1, 2]));
+    assert_eq!(
+        labyrinth.replay(&[0, 1, 2], &Rules::default()).map(|_| ()),
+        Err(Violation::LockedDoor {
+            step: 2,
+            from: 1,
+            to: 2
+        })
+    );
+    let greedy = GreedySolver
+        .solve(&labyrinth, &problem())
+        .outcome
+        .into_inner()
+        .unwrap();
+    assert_eq!(greedy.walk, [0, 9, 0, 1, 2]);
+    assert_eq!(check_walk(&labyrinth, &greedy), Ok(()));
+}
+
+/// The greedy solver isn't optimal, on this maze it goes round through the second row where
+/// the first one is shorter. A fix for the greedy solver updates the walk here
+#[test]
+fn greedy_takes_a_known_detour() {
+    let labyrinth = maze(8, 2);
+    let greedy = GreedySolver
+        .solve(&labyrinth, &problem())
+        .outcome
+        .into_inner()
+        .unwrap();
+    assert_eq!(greedy.walk, [0, 1, 10, 11, 12, 13, 4, 5, 14, 15, 16, 17]);
+    assert_eq!(check_walk(&labyrinth, &greedy), Ok(()));
+    assert_eq!(brute_force(&labyrinth), Some(10));
+}
+
+#[test]
+fn sample_maze_is_solved_optimally() {
+    let labyrinth = lavirint::read_file("./labyrinth.txt").unwrap();
+    let problem = Problem {
+        goals: vec![47],
+        ..problem()
+    };
+    assert_eq!(brute_force(&labyrinth), Some(10));
+    for solver in solvers() {
+        let solution = solver
+            .solve(&labyrinth, &problem)
+            .outcome
+            .into_inner()
+            .unwrap();
+        assert_eq!(solution.walk.len(), 10, "{}", solver.name());
+        assert_eq!(check_walk(&labyrinth, &solution), Ok(()));
+    }
+}
+
+#[test]
+fn per_thread_counts_add_up_over_goals() {
+    let labyrinth = lavirint::read_file("./labyrinth.txt").unwrap();
+    let parallel = ParallelSolver(ParallelConfig {
+        threads: 2,
+        deterministic: false,
+    });
+    // two end fields, a search for each
+    let search = parallel.solve(&labyrinth, &problem()).stats.search;
+    assert_eq!(search.per_thread.len(), 2);
+    assert_eq!(search.per_thread.iter().sum::<usize>(), search.expanded);
+}
+
+#[test]
+fn key_on_the_start_field_isnt_picked_up() {
+    // 0 has a key and leads east to an end, the rest of the row is walled off
+    let mut lines = vec!["0100 0000 1100", "1000 0000 0011"];
+    lines.extend(["0000 0000 0000"; 7]);
+    let labyrinth: Labyrinth = lines.join("\n").parse().unwrap();
+    for solver in solvers() {
+        let solution = solver
+            .solve(&labyrinth, &problem())
+            .outcome
+            .into_inner()
+            .unwrap();
+        assert_eq!(solution.walk, [0, 1], "{}", solver.name());
+        assert!(solution.keys.is_empty(), "{}", solver.name());
+        assert_eq!(check_walk(&labyrinth, &solution), Ok(()));
+    }
+}
+
+#[test]
+fn key_on_the_start_field_doesnt_open_doors() {
+    // 0 has a key, 0 - 1 is a path and 1 = 2 a door to the end
+    let mut lines = vec!["0100 0000 1100", "1100 0100 0000", "1000 0000 0011"];
+    lines.extend(["0000 0000 0000"; 6]);
+    let labyrinth: Labyrinth = lines.join("\n").parse().unwrap();
+    for solver in solvers() {
+        let report = solver.solve(&labyrinth, &problem());
+        assert_eq!(report.outcome, Outcome::Complete(None), "{}", solver.name());
+    }
+}
+
+/// Returns a fixed walk whatever it's asked
+struct Fixed(Vec<usize>);
+
+impl Solver for Fixed {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+    fn solve_to(
+        &self,
+        _: &[Vec<u8>],
+        _: &[bool],
+        _: usize,
+        _: usize,
+        _: &Limits,
+        _: &mut SearchStats,
+    ) -> Outcome<Option<Vec<usize>>> {
+        Outcome::Complete(Some(self.0.clone()))
+    }
+}
+
+#[test]
+fn walks_from_elsewhere_or_short_of_the_goal_are_dropped() {
+    // 0 - 1 - 2 along the first row, the end on 2
+    let mut lines = vec!["0100 0000 0000", "1100 0000 0000", "1000 0000 0011"];
+    lines.extend(["0000 0000 0000"; 6]);
+    let labyrinth: Labyrinth = lines.join("\n").parse().unwrap();
+    let solve = |walk: &[usize]| Fixed(walk.to_vec()).solve(&labyrinth, &problem()).outcome;
+    assert_eq!(solve(&[1, 2]), Outcome::Complete(None));
+    assert_eq!(solve(&[0, 1]), Outcome::Complete(None));
+    assert_eq!(solve(&[0, 2]), Outcome::Complete(None));
+    let solution = solve(&[0, 1, 2]).into_inner().unwrap();
+    assert_eq!(check_walk(&labyrinth, &solution), Ok(()));
+}
+
+#[test]
+fn stats_are_reported_without_a_walk() {
+    // 0 and 1 are joined, the end on 2 can't be reached
+    let mut lines = vec!["0100 0000 0000", "1000 0000 0000", "0000 0000 0011"];
+    lines.extend(["0000 0000 0000"; 6]);
+    let labyrinth: Labyrinth = lines.join("\n").parse().unwrap();
+    for solver in solvers() {
+        let report = solver.solve(&labyrinth, &problem());
+        assert_eq!(
+            report.outcome.clone().into_inner(),
+            None,
+            "{}",
+            solver.name()
+        );
+        let json = report.to_json();
+        assert!(
+            json.contains("\"solution\":null,\"stats\":{\"elapsed_us\":"),
+            "{json}"
+        );
+    }
+    let report = BfsSolver.solve(&labyrinth, &problem());
+    assert_eq!(report.stats.search.expanded, 2); // 0 and 1
+}
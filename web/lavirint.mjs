@@ -0,0 +1,102 @@
+// JS side of the WebAssembly build, see src/wasm.rs. Works in browsers and in node:
+//
+//   const lavirint = await Lavirint.instantiate(bytesOrResponse);
+//   const maze = lavirint.load(text);
+//   maze.walk({ goals: [47] }); // [0, 9, 10, ...]
+//   maze.free();
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+export class Lavirint {
+  constructor(instance) {
+    this.exports = instance.exports;
+  }
+
+  // `source` is the bytes of lavirint.wasm or a fetch() response for it
+  static async instantiate(source) {
+    const imports = { env: { lavirint_now: () => performance.now() } };
+    const { instance } =
+      source instanceof Response
+        ? await WebAssembly.instantiateStreaming(source, imports)
+        : await WebAssembly.instantiate(source, imports);
+    return new Lavirint(instance);
+  }
+
+  // Copies the string into the module, which takes ownership of the buffer
+  input(text) {
+    const bytes = encoder.encode(text);
+    const ptr = this.exports.lavirint_alloc(bytes.length);
+    new Uint8Array(this.exports.memory.buffer, ptr, bytes.length).set(bytes);
+    return [ptr, bytes.length];
+  }
+
+  output(len) {
+    const ptr = this.exports.lavirint_output();
+    return decoder.decode(new Uint8Array(this.exports.memory.buffer, ptr, len));
+  }
+
+  json(len) {
+    const value = JSON.parse(this.output(len));
+    if (value && value.error) {
+      throw new Error(value.error);
+    }
+    return value;
+  }
+
+  // Parses the maze file, throws on lines that aren't fields
+  load(text) {
+    const handle = this.exports.lavirint_load(...this.input(text));
+    if (handle < 0) {
+      this.json(this.exports.lavirint_output_len());
+    }
+    return new Maze(this, handle);
+  }
+}
+
+export class Maze {
+  constructor(lavirint, handle) {
+    this.lavirint = lavirint;
+    this.handle = handle;
+  }
+
+  call(name, options) {
+    const lavirint = this.lavirint;
+    const input = lavirint.input(JSON.stringify(options ?? {}));
+    return lavirint.exports[name](this.handle, ...input);
+  }
+
+  // Rows, columns, keys, ends and passages
+  describe() {
+    return this.lavirint.json(this.lavirint.exports.lavirint_describe(this.handle));
+  }
+
+  // `{ solver, start, goals, ignore_doors, timeout_ms }`, all optional, answers like `--json`
+  solve(options) {
+    return this.lavirint.json(this.call("lavirint_solve", options));
+  }
+
+  // The shortest walk as field numbers, null when there is none
+  walk(options) {
+    return this.solve(options).solution?.walk ?? null;
+  }
+
+  // `{ walk, ignore_doors }`, the trace of a valid walk or the first illegal move
+  replay(options) {
+    return this.lavirint.json(this.call("lavirint_replay", options));
+  }
+
+  // `{ walk }` is drawn on top when given
+  svg(options) {
+    const len = this.call("lavirint_svg", options);
+    const text = this.lavirint.output(len);
+    if (text.startsWith("{")) {
+      this.lavirint.json(len);
+    }
+    return text;
+  }
+
+  free() {
+    this.lavirint.exports.lavirint_unload(this.handle);
+  }
+}
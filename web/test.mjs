@@ -0,0 +1,40 @@
+// Runs the WebAssembly build headless in node:
+//
+//   cargo build --release --lib --target wasm32-unknown-unknown
+//   node web/test.mjs
+
+import assert from "node:assert/strict";
+import { readFile } from "node:fs/promises";
+import { Lavirint } from "./lavirint.mjs";
+
+const root = new URL("../", import.meta.url);
+const wasm = await readFile(new URL("target/wasm32-unknown-unknown/release/lavirint.wasm", root));
+const text = await readFile(new URL("labyrinth.txt", root), "utf8");
+const lavirint = await Lavirint.instantiate(wasm);
+const maze = lavirint.load(text);
+
+const description = maze.describe();
+assert.equal(description.rows, 6);
+assert.deepEqual(description.ends, [8, 47]);
+
+// the same walk the native solvers find
+const expected = [0, 9, 10, 19, 10, 11, 20, 29, 38, 47];
+assert.deepEqual(maze.walk({ goals: [47] }), expected);
+for (const solver of ["bfs", "greedy", "parallel"]) {
+  const { partial, solution } = maze.solve({ solver, goals: [47], threads: 4, timeout_ms: 5000 });
+  assert.equal(partial, false, solver);
+  assert.equal(solution.cost, 9, solver);
+}
+assert.deepEqual(maze.solve({ timeout_ms: 0 }).partial, true);
+
+const { valid, trace } = maze.replay({ walk: expected });
+assert.ok(valid && trace.at_end);
+assert.equal(maze.replay({ walk: [0, 2] }).valid, false);
+assert.ok(maze.svg({ walk: expected }).includes("<polyline"));
+
+assert.throws(() => lavirint.load("nonsense"), /line 1 isn't a field/);
+assert.throws(() => maze.solve({ solver: "dfs" }), /unknown solver dfs/);
+maze.free();
+assert.throws(() => maze.describe(), /no maze with handle/);
+
+console.log("wasm ok");